@@ -0,0 +1,59 @@
+use std::{
+    env,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The environment variable which forces a mode. Its value can be `embed` or `filesystem`.
+const MODE_ENV: &str = "ROCKET_INCLUDE_STATIC_RESOURCES_MODE";
+
+/// The environment variable of reproducible builds, which is used as the build time if it is set.
+const SOURCE_DATE_EPOCH_ENV: &str = "SOURCE_DATE_EPOCH";
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed={}", MODE_ENV);
+    println!("cargo:rerun-if-env-changed={}", SOURCE_DATE_EPOCH_ENV);
+    println!("cargo:rustc-check-cfg=cfg(static_resources_embed)");
+
+    let build_time = env::var(SOURCE_DATE_EPOCH_ENV)
+        .ok()
+        .and_then(|epoch| epoch.trim().parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+        });
+
+    println!("cargo:rustc-env=ROCKET_INCLUDE_STATIC_RESOURCES_BUILD_TIME={}", build_time);
+
+    let embed = match env::var(MODE_ENV) {
+        Ok(mode) => match mode.trim().to_ascii_lowercase().as_str() {
+            "embed" => true,
+            "filesystem" => false,
+            _ => panic!("`{}` should be `embed` or `filesystem`, but it is {:?}.", MODE_ENV, mode),
+        },
+        Err(_) => {
+            let force_embed = env::var_os("CARGO_FEATURE_EMBED").is_some();
+            let force_filesystem = env::var_os("CARGO_FEATURE_FILESYSTEM").is_some();
+
+            match (force_embed, force_filesystem) {
+                (true, false) => true,
+                (false, true) => false,
+                (force_embed, _) => {
+                    if force_embed {
+                        println!(
+                            "cargo:warning=The `embed` and `filesystem` features are both \
+                             enabled, so `debug_assertions` decides the mode. Set `{}` to choose \
+                             one of them.",
+                            MODE_ENV
+                        );
+                    }
+
+                    env::var_os("CARGO_CFG_DEBUG_ASSERTIONS").is_none()
+                },
+            }
+        },
+    };
+
+    if embed {
+        println!("cargo:rustc-cfg=static_resources_embed");
+    }
+}
@@ -0,0 +1,108 @@
+/*!
+# Compression for Include Static Resources for Rocket Framework
+
+This crate is used by the `rocket-include-static-resources` crate and its procedural macros, so that data compressed at compile time and at runtime are the same. Do not use it directly.
+*/
+
+use mime::Mime;
+
+/// Compress `data` with the encoding of the content-coding token `token` (`br`, `zstd` or `gzip`). Returns `None` if the encoding is not enabled or the compressed data is not smaller than the original one.
+pub fn compress(token: &str, data: &[u8]) -> Option<Vec<u8>> {
+    let compressed = match token {
+        "br" => compress_brotli(data),
+        "zstd" => compress_zstd(data),
+        "gzip" => compress_gzip(data),
+        _ => None,
+    }?;
+
+    if compressed.len() < data.len() {
+        Some(compressed)
+    } else {
+        None
+    }
+}
+
+#[cfg(feature = "brotli")]
+fn compress_brotli(data: &[u8]) -> Option<Vec<u8>> {
+    use std::io::Write;
+
+    let mut output = Vec::with_capacity(data.len() / 2);
+
+    {
+        let mut writer = brotli::CompressorWriter::new(&mut output, 4096, 9, 22);
+
+        writer.write_all(data).ok()?;
+    }
+
+    Some(output)
+}
+
+#[cfg(not(feature = "brotli"))]
+#[inline]
+fn compress_brotli(_data: &[u8]) -> Option<Vec<u8>> {
+    None
+}
+
+#[cfg(feature = "zstd")]
+#[inline]
+fn compress_zstd(data: &[u8]) -> Option<Vec<u8>> {
+    zstd::bulk::compress(data, 15).ok()
+}
+
+#[cfg(not(feature = "zstd"))]
+#[inline]
+fn compress_zstd(_data: &[u8]) -> Option<Vec<u8>> {
+    None
+}
+
+#[cfg(feature = "gzip")]
+fn compress_gzip(data: &[u8]) -> Option<Vec<u8>> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(
+        Vec::with_capacity(data.len() / 2),
+        flate2::Compression::best(),
+    );
+
+    encoder.write_all(data).ok()?;
+
+    encoder.finish().ok()
+}
+
+#[cfg(not(feature = "gzip"))]
+#[inline]
+fn compress_gzip(_data: &[u8]) -> Option<Vec<u8>> {
+    None
+}
+
+/// Whether it is worth compressing data of this MIME type.
+pub fn is_compressible(mime: &Mime) -> bool {
+    if mime.type_() == mime::TEXT {
+        return true;
+    }
+
+    if let Some(suffix) = mime.suffix() {
+        if suffix == mime::JSON || suffix == mime::XML {
+            return true;
+        }
+    }
+
+    match (mime.type_().as_str(), mime.subtype().as_str()) {
+        ("application", subtype) => matches!(
+            subtype,
+            "javascript"
+                | "x-javascript"
+                | "ecmascript"
+                | "json"
+                | "xml"
+                | "wasm"
+                | "manifest"
+                | "x-font-ttf"
+                | "x-font-otf"
+                | "vnd.ms-fontobject"
+        ),
+        ("image", subtype) => matches!(subtype, "svg" | "x-icon" | "vnd.microsoft.icon" | "bmp"),
+        ("font", subtype) => matches!(subtype, "ttf" | "otf" | "collection"),
+        _ => false,
+    }
+}
@@ -0,0 +1,8 @@
+/// The environment variable of reproducible builds, which is used as the modification time of embedded files if it is set.
+const SOURCE_DATE_EPOCH_ENV: &str = "SOURCE_DATE_EPOCH";
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    // proc macros cannot make Cargo track the environment variables they read on stable Rust, so this crate is rebuilt when it changes, which rebuilds the crates using the macros as well
+    println!("cargo:rerun-if-env-changed={}", SOURCE_DATE_EPOCH_ENV);
+}
@@ -0,0 +1,401 @@
+/*!
+# Procedural Macros for Include Static Resources for Rocket Framework
+
+This crate is used by the `rocket-include-static-resources` crate. Do not use it directly.
+*/
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use glob::{MatchOptions, Pattern};
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, Span, TokenStream as TokenStream2};
+use quote::quote;
+use rocket_include_static_resources_compression::{compress, is_compressible};
+use syn::{
+    bracketed, parenthesized,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Ident, LitStr, Token,
+};
+
+/// Content codings which embedded files are compressed with, as `(content-coding token, sidecar file extension)`, in the order the server prefers them.
+const ENCODINGS: [(&str, &str); 3] = [("br", "br"), ("zstd", "zst"), ("gzip", "gz")];
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive:              true,
+    require_literal_separator:   true,
+    require_literal_leading_dot: false,
+};
+
+/// Parse a path, which is a string literal or a tuple of string literals to be joined.
+fn parse_path(input: ParseStream) -> syn::Result<LitStr> {
+    if input.peek(syn::token::Paren) {
+        let content;
+
+        parenthesized!(content in input);
+
+        let components = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+
+        let joined = components.iter().map(|c| c.value()).collect::<Vec<_>>().join("/");
+
+        let span = components.first().map(LitStr::span).unwrap_or_else(Span::call_site);
+
+        Ok(LitStr::new(&joined, span))
+    } else {
+        input.parse::<LitStr>()
+    }
+}
+
+struct EmbedFile {
+    path: LitStr,
+}
+
+impl Parse for EmbedFile {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = parse_path(input)?;
+
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+
+        Ok(EmbedFile {
+            path,
+        })
+    }
+}
+
+struct EmbedDirectory {
+    path:    LitStr,
+    include: Vec<LitStr>,
+    exclude: Vec<LitStr>,
+}
+
+impl Parse for EmbedDirectory {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = parse_path(input)?;
+
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+
+            if input.is_empty() {
+                break;
+            }
+
+            let key = input.parse::<Ident>()?;
+
+            input.parse::<Token![=]>()?;
+
+            let content;
+
+            bracketed!(content in input);
+
+            let patterns: Vec<LitStr> =
+                Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?.into_iter().collect();
+
+            for pattern in patterns.iter() {
+                if let Err(err) = Pattern::new(&pattern.value()) {
+                    return Err(syn::Error::new(pattern.span(), err));
+                }
+            }
+
+            if key == "include" {
+                include.extend(patterns);
+            } else if key == "exclude" {
+                exclude.extend(patterns);
+            } else {
+                return Err(syn::Error::new(key.span(), "expected `include` or `exclude`"));
+            }
+        }
+
+        Ok(EmbedDirectory {
+            path,
+            include,
+            exclude,
+        })
+    }
+}
+
+/// Compute the SHA-256, SHA-384 and SHA-512 digests of data as an `EmbeddedDigests` expression, so that they are not computed at runtime.
+#[cfg(feature = "integrity")]
+fn digests(data: &[u8]) -> TokenStream2 {
+    use sha2::{Digest, Sha256, Sha384, Sha512};
+
+    let sha256 = Literal::byte_string(&Sha256::digest(data));
+    let sha384 = Literal::byte_string(&Sha384::digest(data));
+    let sha512 = Literal::byte_string(&Sha512::digest(data));
+
+    quote! {
+        Some((*#sha256, *#sha384, *#sha512))
+    }
+}
+
+/// Digests are computed at runtime without the `integrity` feature.
+#[cfg(not(feature = "integrity"))]
+#[inline]
+fn digests(_data: &[u8]) -> TokenStream2 {
+    quote! { None }
+}
+
+/// Embed the encoded variants of the file at `path` as an `EmbeddedVariants` expression. A sidecar file found by `sidecar` (e.g. **app.js.br** next to **app.js**) is embedded as it is. Otherwise, if the file is compressible, it is compressed with the encoding here, so that nothing is compressed at runtime.
+fn variants(path: &Path, data: &[u8], sidecar: impl Fn(&str) -> Option<PathBuf>) -> TokenStream2 {
+    let compressible = is_compressible(&mime_guess::from_path(path).first_or_octet_stream());
+
+    let variants = ENCODINGS.iter().filter_map(|&(token, extension)| match sidecar(extension) {
+        Some(sidecar) => {
+            let digests = digests(&fs::read(&sidecar).ok()?);
+
+            let sidecar = sidecar.to_string_lossy();
+
+            Some(quote! {
+                (#token, include_bytes!(#sidecar), #digests)
+            })
+        },
+        None if compressible => {
+            let compressed = compress(token, data)?;
+
+            let digests = digests(&compressed);
+
+            let compressed = Literal::byte_string(&compressed);
+
+            Some(quote! {
+                (#token, #compressed, #digests)
+            })
+        },
+        None => None,
+    });
+
+    quote! {
+        &[#(#variants),*]
+    }
+}
+
+/// The modification time of a file as seconds since the Unix epoch in an `Option<u64>` expression, or `SOURCE_DATE_EPOCH` if it is set, so that builds stay reproducible.
+///
+/// `SOURCE_DATE_EPOCH` is tracked by the build script, because reading it here is not tracked by Cargo.
+fn modified(path: &Path) -> TokenStream2 {
+    let modified = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse::<u64>().ok())
+        .or_else(|| {
+            let modified = path.metadata().and_then(|metadata| metadata.modified()).ok()?;
+
+            modified.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+        });
+
+    match modified {
+        Some(modified) => quote! { Some(#modified) },
+        None => quote! { None },
+    }
+}
+
+/// Collect the files under `directory` recursively, with their paths relative to `root` (separated by `/`).
+fn collect_files(root: &Path, directory: &Path, files: &mut Vec<(String, PathBuf)>) {
+    let mut entries: Vec<PathBuf> = match fs::read_dir(directory) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect(),
+        Err(_) => return,
+    };
+
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            collect_files(root, &path, files);
+        } else if path.is_file() {
+            if let Ok(relative_path) = path.strip_prefix(root) {
+                let relative_path = relative_path
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+
+                files.push((relative_path, path));
+            }
+        }
+    }
+}
+
+/// Embed a file (relative to the directory containing the manifest of your package) as a `(&'static [u8], EmbeddedDigests, EmbeddedVariants, Option<u64>)` tuple of its data, digests, encoded variants and modification time (see `modified`). Sidecar files next to it (e.g. **app.js.br** next to **app.js**) are embedded as its encoded variants.
+#[proc_macro]
+pub fn embed_file(input: TokenStream) -> TokenStream {
+    let EmbedFile {
+        path,
+    } = parse_macro_input!(input as EmbedFile);
+
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap_or_default();
+
+    let file = manifest_dir.join(path.value());
+
+    let data = match fs::read(&file) {
+        Ok(data) if file.is_file() => data,
+        _ => {
+            return syn::Error::new(
+                path.span(),
+                format!("{:?} is not a file", file.to_string_lossy()),
+            )
+            .to_compile_error()
+            .into()
+        },
+    };
+
+    let digests = digests(&data);
+
+    let variants = variants(&file, &data, |extension| {
+        let mut sidecar = file.clone().into_os_string();
+
+        sidecar.push(".");
+        sidecar.push(extension);
+
+        Some(PathBuf::from(sidecar)).filter(|sidecar| sidecar.is_file())
+    });
+
+    let modified = modified(&file);
+
+    let file = file.to_string_lossy();
+
+    quote! {
+        {
+            const FILE: (&[u8], Option<([u8; 32], [u8; 48], [u8; 64])>, &[(&str, &[u8], Option<([u8; 32], [u8; 48], [u8; 64])>)], Option<u64>) = (include_bytes!(#file), #digests, #variants, #modified);
+
+            FILE
+        }
+    }
+    .into()
+}
+
+/// Embed the files in a directory (relative to the directory containing the manifest of your package) as a `&'static [(&'static str, &'static [u8], EmbeddedDigests, EmbeddedVariants, Option<u64>)]` slice of relative paths, data, digests, encoded variants and modification times. Only files matched by any `include` pattern (if there is any) and not matched by any `exclude` pattern are embedded. Sidecar files (e.g. **app.js.br** next to **app.js**) are embedded as the encoded variants of their original files instead of files.
+#[proc_macro]
+pub fn embed_directory(input: TokenStream) -> TokenStream {
+    let EmbedDirectory {
+        path,
+        include,
+        exclude,
+    } = parse_macro_input!(input as EmbedDirectory);
+
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap_or_default();
+
+    let directory = manifest_dir.join(path.value());
+
+    if !directory.is_dir() {
+        return syn::Error::new(
+            path.span(),
+            format!("{:?} is not a directory", directory.to_string_lossy()),
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let include: Vec<Pattern> =
+        include.iter().filter_map(|p| Pattern::new(&p.value()).ok()).collect();
+    let exclude: Vec<Pattern> =
+        exclude.iter().filter_map(|p| Pattern::new(&p.value()).ok()).collect();
+
+    let mut files = Vec::new();
+
+    collect_files(&directory, &directory, &mut files);
+
+    let files: Vec<(String, PathBuf)> = files
+        .into_iter()
+        .filter(|(relative_path, _)| {
+            (include.is_empty()
+                || include.iter().any(|p| p.matches_with(relative_path, MATCH_OPTIONS)))
+                && !exclude.iter().any(|p| p.matches_with(relative_path, MATCH_OPTIONS))
+        })
+        .collect();
+
+    let find = |relative_path: &str| {
+        files.iter().find(|(p, _)| p == relative_path).map(|(_, path)| path.clone())
+    };
+
+    let is_sidecar = |relative_path: &str| {
+        ENCODINGS.iter().any(|(_, extension)| {
+            relative_path
+                .strip_suffix(extension)
+                .and_then(|original_path| original_path.strip_suffix('.'))
+                .map_or(false, |original_path| find(original_path).is_some())
+        })
+    };
+
+    let mut entries = Vec::with_capacity(files.len());
+
+    for (relative_path, file) in files.iter() {
+        if is_sidecar(relative_path) {
+            continue;
+        }
+
+        let data = match fs::read(file) {
+            Ok(data) => data,
+            Err(err) => {
+                return syn::Error::new(
+                    path.span(),
+                    format!("{:?} cannot be read: {}", file.to_string_lossy(), err),
+                )
+                .to_compile_error()
+                .into()
+            },
+        };
+
+        let digests = digests(&data);
+
+        let variants =
+            variants(file, &data, |extension| find(&format!("{}.{}", relative_path, extension)));
+
+        let modified = modified(file);
+
+        let file = file.to_string_lossy();
+
+        entries.push(quote! {
+            (#relative_path, include_bytes!(#file), #digests, #variants, #modified)
+        });
+    }
+
+    quote! {
+        {
+            const FILES: &[(&str, &[u8], Option<([u8; 32], [u8; 48], [u8; 64])>, &[(&str, &[u8], Option<([u8; 32], [u8; 48], [u8; 64])>)], Option<u64>)] = &[#(#entries),*];
+
+            FILES
+        }
+    }
+    .into()
+}
+
+/// Parse the arguments of `dir(...)` like `embed_directory!`, as a `(&'static str, &'static [&'static str], &'static [&'static str])` tuple of the absolute path of the directory and its `include` and `exclude` patterns, so that the filesystem mode accepts the same arguments as the embedded mode.
+#[proc_macro]
+pub fn directory_options(input: TokenStream) -> TokenStream {
+    let EmbedDirectory {
+        path,
+        include,
+        exclude,
+    } = parse_macro_input!(input as EmbedDirectory);
+
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap_or_default();
+
+    let directory = manifest_dir.join(path.value());
+
+    if !directory.is_dir() {
+        return syn::Error::new(
+            path.span(),
+            format!("{:?} is not a directory", directory.to_string_lossy()),
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let directory = directory.to_string_lossy();
+
+    quote! {
+        {
+            const OPTIONS: (&str, &[&str], &[&str]) = (#directory, &[#(#include),*], &[#(#exclude),*]);
+
+            OPTIONS
+        }
+    }
+    .into()
+}
@@ -0,0 +1,245 @@
+use std::{
+    collections::HashSet,
+    io::{self, Cursor, ErrorKind, Read},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::encoding::Encoding;
+
+/// A file in an archive.
+#[derive(Debug)]
+pub(crate) struct ArchiveFile {
+    /// The path of the file in the archive (separated by `/`, without a leading `./` or `/`).
+    pub(crate) name:     String,
+    pub(crate) data:     Vec<u8>,
+    /// The modification time recorded in the archive, if any.
+    pub(crate) modified: Option<SystemTime>,
+    /// The data of its sidecar files in the archive (e.g. **app.js.br** next to **app.js**).
+    pub(crate) encoded:  Vec<(Encoding, Vec<u8>)>,
+}
+
+/// Read the files in a zip, tar or tar.gz archive, which is detected by its content. Directories and other special entries are skipped. Sidecar files (e.g. **app.js.br** next to **app.js**) become the encoded variants of their original files instead of files, like in a directory.
+pub(crate) fn read_archive(data: &[u8]) -> Result<Vec<ArchiveFile>, io::Error> {
+    let files = if data.starts_with(b"PK\x03\x04") || data.starts_with(b"PK\x05\x06") {
+        read_zip(data)?
+    } else if data.starts_with(&[0x1F, 0x8B]) {
+        read_tar(flate2::read::GzDecoder::new(data))?
+    } else {
+        read_tar(data)?
+    };
+
+    Ok(group_sidecars(files))
+}
+
+/// Move sidecar files into the encoded variants of their original files.
+fn group_sidecars(files: Vec<ArchiveFile>) -> Vec<ArchiveFile> {
+    let names: HashSet<String> = files.iter().map(|file| file.name.clone()).collect();
+
+    let (sidecars, mut files): (Vec<ArchiveFile>, Vec<ArchiveFile>) =
+        files.into_iter().partition(|file| match Encoding::split_sidecar_path(&file.name) {
+            Some((original_name, _)) => names.contains(original_name),
+            None => false,
+        });
+
+    for sidecar in sidecars {
+        if let Some((original_name, encoding)) = Encoding::split_sidecar_path(&sidecar.name) {
+            // the original file may be a sidecar file itself, such as **app.js.br** for **app.js.br.gz**
+            if let Some(file) = files.iter_mut().find(|file| file.name == original_name) {
+                file.encoded.push((encoding, sidecar.data));
+            }
+        }
+    }
+
+    files
+}
+
+/// Normalize the path of an entry into a resource name. Returns `None` if it is not a relative path to a file.
+#[inline]
+fn entry_name(path: &str) -> Option<String> {
+    let mut segments = Vec::new();
+
+    for segment in path.split(['/', '\\']) {
+        match segment {
+            "" | "." => (),
+            ".." => return None,
+            _ => segments.push(segment),
+        }
+    }
+
+    if segments.is_empty() || path.ends_with('/') {
+        None
+    } else {
+        Some(segments.join("/"))
+    }
+}
+
+/// Read the data of an entry. The size in its header is not trusted for allocating the buffer, because the archive may be corrupt.
+#[inline]
+fn read_entry<R: Read>(mut entry: R) -> Result<Vec<u8>, io::Error> {
+    let mut data = Vec::new();
+
+    entry.read_to_end(&mut data)?;
+
+    Ok(data)
+}
+
+fn read_tar<R: Read>(reader: R) -> Result<Vec<ArchiveFile>, io::Error> {
+    let mut archive = tar::Archive::new(reader);
+
+    let mut files = Vec::new();
+
+    for entry in archive.entries()? {
+        let entry = entry?;
+
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let name = match entry.path()?.to_str().and_then(entry_name) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let modified =
+            entry.header().mtime().ok().map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+
+        files.push(ArchiveFile {
+            name,
+            data: read_entry(entry)?,
+            modified,
+            encoded: Vec::new(),
+        });
+    }
+
+    Ok(files)
+}
+
+fn read_zip(data: &[u8]) -> Result<Vec<ArchiveFile>, io::Error> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+
+    let mut files = Vec::with_capacity(archive.len());
+
+    for index in 0..archive.len() {
+        let file =
+            archive.by_index(index).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+
+        if !file.is_file() {
+            continue;
+        }
+
+        let name = match entry_name(file.name()) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let modified = zip_modified(file.last_modified());
+
+        files.push(ArchiveFile {
+            name,
+            data: read_entry(file)?,
+            modified,
+            encoded: Vec::new(),
+        });
+    }
+
+    Ok(files)
+}
+
+/// Convert the modification time of a zip entry, which is recorded without a time zone, as UTC. Returns `None` if it is not a valid date.
+fn zip_modified(date_time: zip::DateTime) -> Option<SystemTime> {
+    let (year, month, day) =
+        (i64::from(date_time.year()), i64::from(date_time.month()), i64::from(date_time.day()));
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // the days since the Unix epoch, from the algorithm `days_from_civil` by Howard Hinnant
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let secs = days * 86400
+        + i64::from(date_time.hour()) * 3600
+        + i64::from(date_time.minute()) * 60
+        + i64::from(date_time.second());
+
+    u64::try_from(secs).ok().map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tar(entries: &[(&str, &[u8], u64)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+
+        for &(path, data, mtime) in entries {
+            let mut header = tar::Header::new_gnu();
+
+            header.set_size(data.len() as u64);
+            header.set_mtime(mtime);
+            header.set_mode(0o644);
+            header.set_cksum();
+
+            builder.append_data(&mut header, path, data).unwrap();
+        }
+
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn tar_entries_keep_their_modification_times() {
+        let files = read_archive(&tar(&[
+            ("./css/style.css", b"body {}", 1_700_000_000),
+            ("js/app.js", b"alert(1);", 1_600_000_000),
+        ]))
+        .unwrap();
+
+        let modified: Vec<(&str, Option<SystemTime>)> =
+            files.iter().map(|file| (file.name.as_str(), file.modified)).collect();
+
+        assert_eq!(
+            vec![
+                ("css/style.css", Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))),
+                ("js/app.js", Some(UNIX_EPOCH + Duration::from_secs(1_600_000_000))),
+            ],
+            modified
+        );
+    }
+
+    #[test]
+    fn sidecar_entries_become_encoded_variants() {
+        let files = read_archive(&tar(&[
+            ("js/app.js", b"alert(1);", 0),
+            ("js/app.js.br", b"brotli", 0),
+            ("js/app.js.gz", b"gzip", 0),
+            ("js/other.js.br", b"orphan", 0),
+        ]))
+        .unwrap();
+
+        let names: Vec<&str> = files.iter().map(|file| file.name.as_str()).collect();
+
+        assert_eq!(vec!["js/app.js", "js/other.js.br"], names);
+        assert_eq!(
+            vec![(Encoding::Brotli, b"brotli".to_vec()), (Encoding::Gzip, b"gzip".to_vec())],
+            files[0].encoded
+        );
+        assert!(files[1].encoded.is_empty());
+    }
+
+    #[test]
+    fn zip_modification_times_are_taken_as_utc() {
+        let date_time = zip::DateTime::from_date_and_time(2024, 2, 29, 12, 34, 56).unwrap();
+
+        assert_eq!(Some(UNIX_EPOCH + Duration::from_secs(1_709_210_096)), zip_modified(date_time));
+
+        let date_time = zip::DateTime::from_date_and_time(1980, 1, 1, 0, 0, 0).unwrap();
+
+        assert_eq!(Some(UNIX_EPOCH + Duration::from_secs(315_532_800)), zip_modified(date_time));
+    }
+}
@@ -0,0 +1,125 @@
+use std::fmt::{self, Display, Formatter};
+
+/// The `Cache-Control` policy of a resource, which can be declared for each entry of `static_resources_initializer!` with `with`.
+///
+/// ```rust,ignore
+/// static_resources_initializer!(
+///     "favicon" => "examples/front-end/images/favicon.ico" with CachePolicy::new().public().max_age(86400),
+/// )
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CachePolicy {
+    public:                 bool,
+    private:                bool,
+    no_store:               bool,
+    immutable:              bool,
+    max_age:                Option<u32>,
+    stale_while_revalidate: Option<u32>,
+}
+
+impl CachePolicy {
+    /// Create an empty policy, which emits no `Cache-Control` header.
+    #[inline]
+    pub const fn new() -> CachePolicy {
+        CachePolicy {
+            public:                 false,
+            private:                false,
+            no_store:               false,
+            immutable:              false,
+            max_age:                None,
+            stale_while_revalidate: None,
+        }
+    }
+
+    /// Allow shared caches to store the response. It overrides `private`.
+    #[inline]
+    pub const fn public(mut self) -> CachePolicy {
+        self.public = true;
+        self.private = false;
+
+        self
+    }
+
+    /// Only allow the browser to store the response. It overrides `public`.
+    #[inline]
+    pub const fn private(mut self) -> CachePolicy {
+        self.private = true;
+        self.public = false;
+
+        self
+    }
+
+    /// Forbid any cache to store the response. Other directives are ignored.
+    #[inline]
+    pub const fn no_store(mut self) -> CachePolicy {
+        self.no_store = true;
+
+        self
+    }
+
+    /// Indicate that the response will not be updated while it is fresh.
+    #[inline]
+    pub const fn immutable(mut self) -> CachePolicy {
+        self.immutable = true;
+
+        self
+    }
+
+    /// Set how long (in seconds) the response stays fresh.
+    #[inline]
+    pub const fn max_age(mut self, seconds: u32) -> CachePolicy {
+        self.max_age = Some(seconds);
+
+        self
+    }
+
+    /// Set how long (in seconds) a stale response can be used while it is revalidated in the background.
+    #[inline]
+    pub const fn stale_while_revalidate(mut self, seconds: u32) -> CachePolicy {
+        self.stale_while_revalidate = Some(seconds);
+
+        self
+    }
+
+    /// Whether the policy has no directive.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        !self.public
+            && !self.private
+            && !self.no_store
+            && !self.immutable
+            && self.max_age.is_none()
+            && self.stale_while_revalidate.is_none()
+    }
+}
+
+impl Display for CachePolicy {
+    /// Format the value of the `Cache-Control` header.
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        if self.no_store {
+            return f.write_str("no-store");
+        }
+
+        let mut directives = Vec::with_capacity(4);
+
+        if self.public {
+            directives.push("public".to_string());
+        } else if self.private {
+            directives.push("private".to_string());
+        }
+
+        if let Some(max_age) = self.max_age {
+            directives.push(format!("max-age={}", max_age));
+        }
+
+        if let Some(stale_while_revalidate) = self.stale_while_revalidate {
+            directives.push(format!("stale-while-revalidate={}", stale_while_revalidate));
+        }
+
+        if self.immutable {
+            directives.push("immutable".to_string());
+        }
+
+        f.write_str(&directives.join(", "))
+    }
+}
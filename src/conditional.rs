@@ -0,0 +1,337 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    rocket::{http::Method, request::Request, response},
+    EntityTag,
+};
+
+/// The result of evaluating the preconditions of a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Precondition {
+    /// Respond the representation.
+    Proceed,
+    /// Respond `304 Not Modified`.
+    NotModified,
+    /// Respond `412 Precondition Failed`.
+    Failed,
+}
+
+/// Parse the value of an `If-Match` or `If-None-Match` header. `None` means `*`.
+fn parse_etag_list(value: &str) -> Option<Vec<EntityTag<'_>>> {
+    let value = value.trim();
+
+    if value == "*" {
+        return None;
+    }
+
+    let mut etags = Vec::new();
+    let mut rest = value;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_ascii_whitespace());
+
+        if rest.is_empty() {
+            break;
+        }
+
+        let start = if rest.starts_with("W/\"") {
+            3
+        } else if rest.starts_with('"') {
+            1
+        } else {
+            // not an entity-tag, skip it
+            match rest.find(',') {
+                Some(index) => {
+                    rest = &rest[index..];
+
+                    continue;
+                },
+                None => break,
+            }
+        };
+
+        match rest[start..].find('"') {
+            Some(index) => {
+                let end = start + index + 1;
+
+                if let Ok(etag) = EntityTag::from_str(&rest[..end]) {
+                    etags.push(etag);
+                }
+
+                rest = &rest[end..];
+            },
+            None => break,
+        }
+    }
+
+    Some(etags)
+}
+
+/// Convert a `SystemTime` to seconds since the Unix epoch, which is the precision of HTTP dates.
+#[inline]
+fn to_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Parse an HTTP date header of a request.
+#[inline]
+fn get_date(request: &Request<'_>, name: &str) -> Option<u64> {
+    request
+        .headers()
+        .get_one(name)
+        .and_then(|value| httpdate::parse_http_date(value.trim()).ok())
+        .map(to_secs)
+}
+
+/// Evaluate `If-Match`, `If-Unmodified-Since`, `If-None-Match` and `If-Modified-Since` in the order defined by RFC 9110, section 13.2.2.
+///
+/// `etag_if_none_match` is the `EtagIfNoneMatch` request guard passed to `StaticContextManager::build`. It is treated as one more entity-tag of `If-None-Match`.
+pub(crate) fn evaluate_preconditions(
+    request: &Request<'_>,
+    etag_if_none_match: Option<&EntityTag<'static>>,
+    etag: &EntityTag<'static>,
+    last_modified: Option<SystemTime>,
+) -> Precondition {
+    let headers = request.headers();
+
+    match headers.get_one("If-Match") {
+        Some(if_match) => {
+            if let Some(etags) = parse_etag_list(if_match) {
+                if !etags.iter().any(|e| e.strong_eq(etag)) {
+                    return Precondition::Failed;
+                }
+            }
+        },
+        None => {
+            if let (Some(since), Some(last_modified)) =
+                (get_date(request, "If-Unmodified-Since"), last_modified)
+            {
+                if to_secs(last_modified) > since {
+                    return Precondition::Failed;
+                }
+            }
+        },
+    }
+
+    let if_none_match = headers.get_one("If-None-Match");
+
+    if if_none_match.is_some() || etag_if_none_match.is_some() {
+        let matched = etag_if_none_match.map(|e| e.weak_eq(etag)).unwrap_or(false)
+            || match if_none_match.map(parse_etag_list) {
+                Some(Some(etags)) => etags.iter().any(|e| e.weak_eq(etag)),
+                Some(None) => true,
+                None => false,
+            };
+
+        if matched {
+            return match request.method() {
+                Method::Get | Method::Head => Precondition::NotModified,
+                _ => Precondition::Failed,
+            };
+        }
+    } else if let (Some(since), Some(last_modified)) =
+        (get_date(request, "If-Modified-Since"), last_modified)
+    {
+        if matches!(request.method(), Method::Get | Method::Head) && to_secs(last_modified) <= since
+        {
+            return Precondition::NotModified;
+        }
+    }
+
+    Precondition::Proceed
+}
+
+/// Whether the `If-Range` header (if any) allows the `Range` header to be applied.
+pub(crate) fn if_range_matches(
+    request: &Request<'_>,
+    etag: &EntityTag<'static>,
+    last_modified: Option<SystemTime>,
+) -> bool {
+    match request.headers().get_one("If-Range") {
+        Some(if_range) => {
+            let if_range = if_range.trim();
+
+            match EntityTag::from_str(if_range) {
+                Ok(if_range_etag) => if_range_etag.strong_eq(etag),
+                Err(_) => match (httpdate::parse_http_date(if_range), last_modified) {
+                    (Ok(date), Some(last_modified)) => to_secs(date) == to_secs(last_modified),
+                    _ => false,
+                },
+            }
+        },
+        None => true,
+    }
+}
+
+/// Set the `ETag` and `Last-Modified` headers, which are needed by both `200 OK` and `304 Not Modified` responses.
+#[inline]
+pub(crate) fn set_validators(
+    response: &mut response::Builder<'_>,
+    etag: &EntityTag<'static>,
+    last_modified: Option<SystemTime>,
+) {
+    response.raw_header("Etag", etag.to_string());
+
+    if let Some(last_modified) = last_modified {
+        response.raw_header("Last-Modified", httpdate::fmt_http_date(last_modified));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::rocket::{
+        http::Header,
+        local::blocking::{Client, LocalRequest},
+    };
+
+    const LAST_MODIFIED: u64 = 1_700_000_000;
+
+    fn etag() -> EntityTag<'static> {
+        EntityTag::from_data(b"body { color: red; }")
+    }
+
+    fn last_modified() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(LAST_MODIFIED)
+    }
+
+    fn http_date(secs: u64) -> String {
+        httpdate::fmt_http_date(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    fn client() -> Client {
+        Client::debug_with(Vec::new()).unwrap()
+    }
+
+    fn with_headers<'c>(
+        mut request: LocalRequest<'c>,
+        headers: &[(&str, String)],
+    ) -> LocalRequest<'c> {
+        for (name, value) in headers {
+            request.add_header(Header::new(name.to_string(), value.clone()));
+        }
+
+        request
+    }
+
+    fn evaluate(client: &Client, headers: &[(&str, String)]) -> Precondition {
+        let request = with_headers(client.get("/"), headers);
+
+        evaluate_preconditions(request.inner(), None, &etag(), Some(last_modified()))
+    }
+
+    fn weak(etag: &EntityTag<'static>) -> String {
+        format!("W/{}", etag)
+    }
+
+    #[test]
+    fn if_match_takes_precedence_over_if_none_match() {
+        let client = client();
+        let etag = etag().to_string();
+
+        assert_eq!(
+            Precondition::Failed,
+            evaluate(&client, &[("If-Match", "\"other\"".into()), ("If-None-Match", etag.clone())])
+        );
+        assert_eq!(
+            Precondition::NotModified,
+            evaluate(&client, &[("If-Match", etag.clone()), ("If-None-Match", etag.clone())])
+        );
+        assert_eq!(
+            Precondition::NotModified,
+            evaluate(&client, &[("If-Match", "*".into()), ("If-None-Match", "*".into())])
+        );
+    }
+
+    #[test]
+    fn if_match_uses_the_strong_comparison() {
+        let client = client();
+
+        assert_eq!(Precondition::Failed, evaluate(&client, &[("If-Match", weak(&etag()))]));
+        assert_eq!(
+            Precondition::Proceed,
+            evaluate(&client, &[("If-Match", format!("\"other\", {}", etag()))])
+        );
+    }
+
+    #[test]
+    fn if_none_match_uses_the_weak_comparison() {
+        let client = client();
+
+        assert_eq!(
+            Precondition::NotModified,
+            evaluate(&client, &[("If-None-Match", weak(&etag()))])
+        );
+        assert_eq!(
+            Precondition::Proceed,
+            evaluate(&client, &[("If-None-Match", "\"other\"".into())])
+        );
+
+        let request = with_headers(client.post("/"), &[("If-None-Match", "*".into())]);
+
+        assert_eq!(
+            Precondition::Failed,
+            evaluate_preconditions(request.inner(), None, &etag(), Some(last_modified()))
+        );
+    }
+
+    #[test]
+    fn dates_are_ignored_with_entity_tags() {
+        let client = client();
+
+        // If-Unmodified-Since is ignored if there is If-Match
+        assert_eq!(
+            Precondition::Proceed,
+            evaluate(&client, &[
+                ("If-Match", etag().to_string()),
+                ("If-Unmodified-Since", http_date(LAST_MODIFIED - 60))
+            ])
+        );
+        assert_eq!(
+            Precondition::Failed,
+            evaluate(&client, &[("If-Unmodified-Since", http_date(LAST_MODIFIED - 60))])
+        );
+
+        // If-Modified-Since is ignored if there is If-None-Match
+        assert_eq!(
+            Precondition::Proceed,
+            evaluate(&client, &[
+                ("If-None-Match", "\"other\"".into()),
+                ("If-Modified-Since", http_date(LAST_MODIFIED))
+            ])
+        );
+        assert_eq!(
+            Precondition::NotModified,
+            evaluate(&client, &[("If-Modified-Since", http_date(LAST_MODIFIED))])
+        );
+        assert_eq!(
+            Precondition::Proceed,
+            evaluate(&client, &[("If-Modified-Since", http_date(LAST_MODIFIED - 1))])
+        );
+    }
+
+    #[test]
+    fn if_range() {
+        let client = client();
+
+        let matches = |value: Option<String>| {
+            let request = match value {
+                Some(value) => with_headers(client.get("/"), &[("If-Range", value)]),
+                None => client.get("/"),
+            };
+
+            if_range_matches(request.inner(), &etag(), Some(last_modified()))
+        };
+
+        assert!(matches(None));
+        assert!(matches(Some(etag().to_string())));
+        // weak entity-tags never match
+        assert!(!matches(Some(weak(&etag()))));
+        assert!(!matches(Some("\"other\"".into())));
+        assert!(matches(Some(http_date(LAST_MODIFIED))));
+        assert!(!matches(Some(http_date(LAST_MODIFIED - 1))));
+        assert!(!matches(Some("not a date".into())));
+    }
+}
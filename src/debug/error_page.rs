@@ -0,0 +1,52 @@
+use std::{io, path::Path};
+
+#[cfg(feature = "live-reload")]
+use super::live_reload::inject_script;
+
+/// Escape text for HTML.
+#[inline]
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Render a page which tells why the file of a resource cannot be loaded. With live reloading, the page reloads itself once the file is loaded.
+pub(crate) fn render_error_page(path: &Path, error: &io::Error) -> Vec<u8> {
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="utf-8">
+    <meta name="color-scheme" content="light dark">
+    <title>Static resource unavailable</title>
+</head>
+<body>
+    <h1>Static resource unavailable</h1>
+    <p>The file of this resource cannot be loaded. It is served again as soon as it can be loaded.</p>
+    <p><strong>Path:</strong> <code>{}</code></p>
+    <p><strong>Error:</strong> <code>{}</code></p>
+</body>
+</html>
+"#,
+        escape_html(&path.to_string_lossy()),
+        escape_html(&error.to_string())
+    );
+
+    #[cfg(feature = "live-reload")]
+    return inject_script(html.into_bytes());
+
+    #[cfg(not(feature = "live-reload"))]
+    html.into_bytes()
+}
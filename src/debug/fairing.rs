@@ -1,5 +1,7 @@
-use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::{any::type_name, marker::PhantomData};
 
+#[cfg(feature = "live-reload")]
+use super::live_reload::{self, LiveReloadSources, LIVE_RELOAD_PATH};
 use super::{FileResources, StaticContextManager, StaticResponse};
 use crate::rocket::{
     fairing::{Fairing, Info, Kind},
@@ -8,14 +10,39 @@ use crate::rocket::{
 
 const FAIRING_NAME: &str = "Static Resources (Debug)";
 
-/// The fairing of `StaticResponse`.
-pub struct StaticResponseFairing {
-    #[allow(clippy::type_complexity)]
-    pub(crate) custom_callback: Box<dyn Fn(&mut MutexGuard<FileResources>) + Send + Sync + 'static>,
+/// The fairing of `StaticResponse`. It registers the resources into `StaticContextManager<N>`, where `N` is the namespace of the resources.
+pub struct StaticResponseFairing<N = ()> {
+    pub(crate) custom_callback: Box<dyn Fn(&mut FileResources) + Send + Sync + 'static>,
+    namespace:                  PhantomData<fn() -> N>,
+}
+
+impl<N> StaticResponseFairing<N> {
+    /// Move the resources into the namespace `M`, so that they are managed by `StaticContextManager<M>` independently of other sets of resources.
+    #[inline]
+    pub fn namespace<M>(self) -> StaticResponseFairing<M> {
+        StaticResponseFairing {
+            custom_callback: self.custom_callback,
+            namespace:       PhantomData,
+        }
+    }
+
+    /// Merge the resources registered by another fairing into the namespace of this one, so that both sets of resources are managed by one `StaticContextManager<N>`.
+    #[inline]
+    pub fn merge<M>(self, other: StaticResponseFairing<M>) -> StaticResponseFairing<N> {
+        let (first, second) = (self.custom_callback, other.custom_callback);
+
+        StaticResponseFairing {
+            custom_callback: Box::new(move |resources| {
+                first(resources);
+                second(resources);
+            }),
+            namespace:       PhantomData,
+        }
+    }
 }
 
 #[rocket::async_trait]
-impl Fairing for StaticResponseFairing {
+impl<N: 'static> Fairing for StaticResponseFairing<N> {
     #[inline]
     fn info(&self) -> Info {
         Info {
@@ -25,11 +52,44 @@ impl Fairing for StaticResponseFairing {
 
     #[inline]
     async fn on_ignite(&self, rocket: Rocket<Build>) -> Result<Rocket<Build>, Rocket<Build>> {
-        let resources = Mutex::new(FileResources::new());
+        if rocket.state::<StaticContextManager<N>>().is_some() {
+            rocket::error!(
+                "The static resources of the namespace `{}` are registered by more than one \
+                 fairing. Merge the fairings by `StaticResponseFairing::merge`, or move them into \
+                 different namespaces by `StaticResponseFairing::namespace`.",
+                type_name::<N>()
+            );
+
+            return Err(rocket);
+        }
+
+        let mut resources = FileResources::new();
+
+        (self.custom_callback)(&mut resources);
+
+        let errors = resources.take_errors();
+
+        if !errors.is_empty() {
+            for (name, err) in errors {
+                rocket::error!("Failed to register the static resource `{}`: {}", name, err);
+            }
+
+            return Err(rocket);
+        }
+
+        #[cfg(feature = "live-reload")]
+        let rocket = match rocket.state::<LiveReloadSources>() {
+            Some(sources) => {
+                sources.push(resources.share());
 
-        (self.custom_callback)(&mut resources.lock().unwrap_or_else(PoisonError::into_inner));
+                rocket
+            },
+            None => rocket
+                .manage(LiveReloadSources::new(resources.share()))
+                .mount(LIVE_RELOAD_PATH, live_reload::routes()),
+        };
 
-        let state = StaticContextManager::new(resources);
+        let state = StaticContextManager::<N>::new(resources);
 
         Ok(rocket.manage(state))
     }
@@ -38,11 +98,11 @@ impl Fairing for StaticResponseFairing {
 impl StaticResponse {
     #[inline]
     /// Create the fairing of `HandlebarsResponse`.
-    pub fn fairing<F>(f: F) -> impl Fairing
+    pub fn fairing<F>(f: F) -> StaticResponseFairing
     where
-        F: Fn(&mut MutexGuard<FileResources>) + Send + Sync + 'static, {
+        F: Fn(&mut FileResources) + Send + Sync + 'static, {
         StaticResponseFairing {
-            custom_callback: Box::new(f)
+            custom_callback: Box::new(f), namespace: PhantomData
         }
     }
 }
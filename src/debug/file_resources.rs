@@ -1,159 +1,1831 @@
 use std::{
+    borrow::Cow,
     collections::HashMap,
-    fs,
+    fs::{self, Metadata},
     io::{self, ErrorKind},
-    path::PathBuf,
-    sync::Arc,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
     time::SystemTime,
 };
 
+use arc_swap::{ArcSwap, ArcSwapOption};
+use glob::Pattern;
 use mime::Mime;
 
-use crate::{functions::compute_data_etag, mime, EntityTag};
+#[cfg(feature = "live-reload")]
+use super::live_reload::inject_script;
+#[cfg(feature = "watch")]
+use super::watcher::{Changes, FileWatcher, WatcherState};
+#[cfg(feature = "archive")]
+use crate::archive::read_archive;
+use crate::{
+    encoding::{is_compressible, Encoding},
+    functions::{
+        build_time, collect_files, compile_patterns, compute_data_etag, compute_file_etag,
+        embedded_last_modified, is_matched,
+    },
+    mime,
+    rocket::tokio::task,
+    CachePolicy, EmbeddedDirectoryFile, EmbeddedFile, EntityTag, Error, ResourceData,
+    ResourceStore, ResourceView,
+};
+#[cfg(feature = "integrity")]
+use crate::{DigestHeaders, Integrity};
+
+/// Make a relative path absolute, because the paths noticed by the file watcher are based on the watched paths.
+#[cfg(feature = "watch")]
+#[inline]
+fn absolute_path(path: PathBuf) -> PathBuf {
+    if path.is_absolute() {
+        path
+    } else {
+        match std::env::current_dir() {
+            Ok(current_dir) => current_dir.join(path),
+            Err(_) => path,
+        }
+    }
+}
+
+#[cfg(not(feature = "watch"))]
+#[inline]
+fn absolute_path(path: PathBuf) -> PathBuf {
+    path
+}
+
+/// Create a file watcher. If it fails, files are checked on every request instead.
+#[cfg(feature = "watch")]
+#[inline]
+fn create_watcher() -> Option<FileWatcher> {
+    match FileWatcher::new() {
+        Ok(watcher) => Some(watcher),
+        Err(err) => {
+            rocket::warn!(
+                "Failed to create a file watcher, so files are checked on every request: {}",
+                err
+            );
+
+            None
+        },
+    }
+}
+
+/// The default size in bytes above which files are streamed from the disk instead of being kept in memory.
+const DEFAULT_STREAM_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+/// The metadata which identifies a version of a file. Any change of it makes the file reloaded, even if the modification time goes backward (e.g. `git checkout` or `cp -p`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    mtime:  Option<SystemTime>,
+    length: u64,
+    inode:  Option<u64>,
+}
+
+impl FileStamp {
+    #[inline]
+    fn new(metadata: &Metadata) -> FileStamp {
+        #[cfg(unix)]
+        let inode = Some(std::os::unix::fs::MetadataExt::ino(metadata));
+
+        #[cfg(not(unix))]
+        let inode = None;
+
+        FileStamp {
+            mtime: metadata.modified().ok(),
+            length: metadata.len(),
+            inode,
+        }
+    }
+
+    /// Whether the file may have been changed. Files whose modification times are unknown are always considered changed.
+    #[inline]
+    fn is_changed(&self, metadata: &Metadata) -> bool {
+        self.mtime.is_none() || *self != FileStamp::new(metadata)
+    }
+}
+
+/// How files are loaded and checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Options {
+    /// Files larger than it are streamed.
+    stream_threshold: u64,
+    /// Whether the content of files is compared as well when their metadata has not changed.
+    content_hash:     bool,
+    /// Which digest headers are sent.
+    #[cfg(feature = "integrity")]
+    digest_headers:   DigestHeaders,
+}
+
+/// A `Cache-Control` policy set for a name, or for every name starting with a prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CachePolicyRule {
+    name:         String,
+    prefix:       bool,
+    cache_policy: CachePolicy,
+}
+
+impl CachePolicyRule {
+    #[inline]
+    fn matches(&self, name: &str) -> bool {
+        if self.prefix {
+            name.starts_with(self.name.as_str())
+        } else {
+            name == self.name
+        }
+    }
+}
+
+/// Whether the live reload script is injected into the resource.
+#[inline]
+fn has_injected_script(mime: &Mime) -> bool {
+    cfg!(feature = "live-reload") && mime.type_() == mime::TEXT && mime.subtype() == mime::HTML
+}
+
+/// Prepare the data of a resource. The live reload script is injected into HTML.
+#[inline]
+fn prepare_data(data: Vec<u8>, mime: &Mime) -> Vec<u8> {
+    #[cfg(feature = "live-reload")]
+    if has_injected_script(mime) {
+        return inject_script(data);
+    }
+
+    #[cfg(not(feature = "live-reload"))]
+    let _ = mime;
+
+    data
+}
+
+/// Read the data of a resource from its file.
+#[inline]
+fn read_data(path: &Path, mime: &Mime) -> Result<Vec<u8>, io::Error> {
+    Ok(prepare_data(fs::read(path)?, mime))
+}
+
+/// Counts the changes of resources, so that live reloading can tell what has changed since a moment.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Versions {
+    /// The number of changes.
+    #[cfg(feature = "live-reload")]
+    pub(crate) changed:  u64,
+    /// The number of changes when a resource other than CSS changed last time.
+    #[cfg(feature = "live-reload")]
+    pub(crate) reloaded: u64,
+}
+
+impl Versions {
+    #[inline]
+    fn record(&mut self, mime: &Mime) {
+        #[cfg(feature = "live-reload")]
+        {
+            self.changed += 1;
+
+            if !(mime.type_() == mime::TEXT && mime.subtype() == mime::CSS) {
+                self.reloaded = self.changed;
+            }
+        }
+
+        #[cfg(not(feature = "live-reload"))]
+        let _ = mime;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct EncodedResource {
+    pub(crate) data:      Arc<Vec<u8>>,
+    pub(crate) etag:      EntityTag<'static>,
+    #[cfg(feature = "integrity")]
+    pub(crate) integrity: Integrity,
+}
+
+/// Encoded variants of a resource, compressed (or read from sidecar files) the first time any of them is requested. Loading them blocks the current thread, so `StaticResponse::open_async` does it on a blocking thread of the runtime, and responding only reads the loaded variants.
+#[derive(Debug)]
+pub(crate) struct EncodedResources {
+    path:          Option<PathBuf>,
+    data:          Arc<Vec<u8>>,
+    compressible:  bool,
+    sidecars:      Vec<Encoding>,
+    /// Precompressed data which is not read from sidecar files, such as sidecar files in an archive.
+    precompressed: Vec<(Encoding, Vec<u8>)>,
+    variants:      ArcSwapOption<Vec<(Encoding, EncodedResource)>>,
+    /// Held while the variants are being loaded, so that concurrent requests do not compress the same data twice.
+    loading:       Mutex<()>,
+}
+
+impl EncodedResources {
+    /// No encoded variants, for streamed resources.
+    #[inline]
+    fn none() -> EncodedResources {
+        EncodedResources {
+            path:          None,
+            data:          Arc::new(Vec::new()),
+            compressible:  false,
+            sidecars:      Vec::new(),
+            precompressed: Vec::new(),
+            variants:      ArcSwapOption::empty(),
+            loading:       Mutex::new(()),
+        }
+    }
+
+    #[inline]
+    fn new(
+        path: Option<PathBuf>,
+        mime: &Mime,
+        data: Arc<Vec<u8>>,
+        mut precompressed: Vec<(Encoding, Vec<u8>)>,
+    ) -> EncodedResources {
+        // sidecar files do not contain the injected script
+        let sidecars = match path.as_deref() {
+            Some(path) if !has_injected_script(mime) => Encoding::ALL
+                .iter()
+                .copied()
+                .filter(|&encoding| encoding.sidecar_path(path).is_file())
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        if has_injected_script(mime) {
+            precompressed.clear();
+        }
+
+        EncodedResources {
+            path,
+            data,
+            compressible: is_compressible(mime),
+            sidecars,
+            precompressed,
+            variants: ArcSwapOption::empty(),
+            loading: Mutex::new(()),
+        }
+    }
+
+    /// Whether the resource may have any encoded variant.
+    #[inline]
+    pub(crate) fn has_variants(&self) -> bool {
+        !self.sidecars.is_empty()
+            || !self.precompressed.is_empty()
+            || (self.compressible && Encoding::ALL.iter().any(|encoding| encoding.is_enabled()))
+    }
+
+    /// Whether the variants need to be loaded by `load_all` before they can be served.
+    #[inline]
+    pub(crate) fn needs_loading(&self) -> bool {
+        self.has_variants() && self.variants.load().is_none()
+    }
+
+    /// Load the variants of every encoding if they have not been loaded. It blocks the current thread.
+    pub(crate) fn load_all(&self) {
+        if !self.needs_loading() {
+            return;
+        }
+
+        let _loading = self.loading.lock().unwrap_or_else(PoisonError::into_inner);
+
+        // another thread may have loaded them while this one was waiting
+        if !self.needs_loading() {
+            return;
+        }
+
+        let variants = Encoding::ALL
+            .iter()
+            .filter_map(|&encoding| self.load(encoding).map(|variant| (encoding, variant)))
+            .collect();
+
+        self.variants.store(Some(Arc::new(variants)));
+    }
+
+    /// Get the variant of a specific encoding if the variants have been loaded. It never blocks.
+    #[inline]
+    pub(crate) fn get(&self, encoding: Encoding) -> Option<EncodedResource> {
+        self.variants
+            .load()
+            .as_deref()?
+            .iter()
+            .find(|(e, _)| *e == encoding)
+            .map(|(_, variant)| variant.clone())
+    }
+
+    fn load(&self, encoding: Encoding) -> Option<EncodedResource> {
+        let data = if let Some((_, data)) = self.precompressed.iter().find(|(e, _)| *e == encoding)
+        {
+            data.clone()
+        } else if self.sidecars.contains(&encoding) {
+            fs::read(encoding.sidecar_path(self.path.as_deref()?)).ok()?
+        } else if self.compressible {
+            encoding.compress(&self.data)?
+        } else {
+            return None;
+        };
+
+        let etag = compute_data_etag(&data);
+
+        Some(EncodedResource {
+            #[cfg(feature = "integrity")]
+            integrity: Integrity::from_data(&data),
+            data: Arc::new(data),
+            etag,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Resource {
+    /// `None` for resources registered with their data, which are never reloaded.
+    path:                 Option<PathBuf>,
+    // mime could be an atom `Mime`, so just clone it
+    pub(crate) mime:      Mime,
+    pub(crate) data:      Arc<Vec<u8>>,
+    pub(crate) etag:      EntityTag<'static>,
+    pub(crate) encoded:   Arc<EncodedResources>,
+    pub(crate) mtime:     Option<SystemTime>,
+    /// The digests of `data`, which are `None` if the resource is streamed or has not been loaded.
+    #[cfg(feature = "integrity")]
+    pub(crate) integrity: Option<Integrity>,
+    /// The digests of the streamed file, which are computed on demand and kept until the file changes, because this resource is replaced then.
+    #[cfg(feature = "integrity")]
+    streamed_integrity:   Arc<Mutex<Option<Integrity>>>,
+    stamp:                Option<FileStamp>,
+    /// The length of the file if it is streamed instead of being kept in memory, in which case `data` is empty.
+    streamed:             Option<u64>,
+    /// Why the file failed to be loaded last time. The last good content is kept until the file can be loaded again.
+    pub(crate) error:     Option<Arc<io::Error>>,
+    directory:            Option<usize>,
+    #[cfg(feature = "archive")]
+    archive:              Option<usize>,
+    dirty:                bool,
+}
+
+impl Resource {
+    /// Load a resource from its file. Files larger than `stream_threshold` bytes are not read, but streamed on every request with an ETag derived from their lengths and modification times.
+    fn from_file(
+        path: PathBuf,
+        mime: Mime,
+        metadata: &Metadata,
+        directory: Option<usize>,
+        options: Options,
+    ) -> Result<Resource, io::Error> {
+        let mtime = metadata.modified().ok();
+
+        let (data, etag, encoded, streamed) = if metadata.len() > options.stream_threshold {
+            (
+                Arc::new(Vec::new()),
+                compute_file_etag(metadata.len(), mtime),
+                Arc::new(EncodedResources::none()),
+                Some(metadata.len()),
+            )
+        } else {
+            let data = Arc::new(read_data(&path, &mime)?);
+
+            let etag = compute_data_etag(data.as_slice());
+
+            let encoded = Arc::new(EncodedResources::new(
+                Some(path.clone()),
+                &mime,
+                data.clone(),
+                Vec::new(),
+            ));
+
+            (data, etag, encoded, None)
+        };
+
+        Ok(Resource {
+            #[cfg(feature = "integrity")]
+            integrity: streamed.is_none().then(|| Integrity::from_data(data.as_slice())),
+            #[cfg(feature = "integrity")]
+            streamed_integrity: Arc::default(),
+            path: Some(path),
+            mime,
+            data,
+            etag,
+            encoded,
+            mtime,
+            stamp: Some(FileStamp::new(metadata)),
+            streamed,
+            error: None,
+            directory,
+            #[cfg(feature = "archive")]
+            archive: None,
+            dirty: false,
+        })
+    }
+
+    /// A resource whose file failed to be loaded, which has no content until the file can be loaded.
+    fn failed(path: PathBuf, mime: Mime, directory: Option<usize>, error: io::Error) -> Resource {
+        Resource {
+            path: Some(path),
+            mime,
+            data: Arc::new(Vec::new()),
+            etag: compute_data_etag(&[]),
+            encoded: Arc::new(EncodedResources::none()),
+            mtime: None,
+            #[cfg(feature = "integrity")]
+            integrity: None,
+            #[cfg(feature = "integrity")]
+            streamed_integrity: Arc::default(),
+            stamp: None,
+            streamed: None,
+            error: Some(Arc::new(error)),
+            directory,
+            #[cfg(feature = "archive")]
+            archive: None,
+            dirty: false,
+        }
+    }
+
+    /// Whether the resource has any content, which is false if its file has never been loaded.
+    #[inline]
+    pub(crate) fn is_loaded(&self) -> bool {
+        self.path.is_none() || self.stamp.is_some()
+    }
+
+    /// The path of the file and the error if the resource has no content to serve because its file cannot be loaded.
+    #[inline]
+    pub(crate) fn load_error(&self) -> Option<(&Path, &Arc<io::Error>)> {
+        match (self.path.as_deref(), self.error.as_ref()) {
+            (Some(path), Some(error)) if !self.is_loaded() => Some((path, error)),
+            _ => None,
+        }
+    }
+
+    /// The path and the length of the file if the resource is streamed.
+    #[inline]
+    pub(crate) fn streamed_length(&self) -> Option<(&Path, u64)> {
+        match (self.path.as_deref(), self.streamed) {
+            (Some(path), Some(length)) => Some((path, length)),
+            _ => None,
+        }
+    }
+
+    /// A view of the resource with its data.
+    #[inline]
+    fn view(&self, data: Arc<Vec<u8>>) -> ResourceView {
+        ResourceView {
+            mime:          self.mime.clone(),
+            data:          ResourceData::Shared(data),
+            etag:          self.etag.clone(),
+            encoding:      None,
+            last_modified: self.mtime,
+        }
+    }
+
+    /// Return `Err(Error::IOError)` if the resource has no content because its file cannot be loaded.
+    #[inline]
+    pub(crate) fn check_loaded(&self) -> Result<(), Error> {
+        match self.load_error() {
+            Some((_, error)) => Err(Error::IOError(io::Error::new(error.kind(), error.clone()))),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the data, reading it from the file if the resource is streamed.
+    #[inline]
+    pub(crate) fn read_data(&self) -> Result<Arc<Vec<u8>>, io::Error> {
+        match self.streamed_length() {
+            Some((path, _)) => Ok(Arc::new(fs::read(path)?)),
+            None => Ok(self.data.clone()),
+        }
+    }
+
+    /// Get the digests of the data. A streamed file is hashed while being read, without the lock of the resources, and its digests are kept if it has not been changed since the resource was loaded.
+    #[cfg(feature = "integrity")]
+    pub(crate) fn integrity(&self) -> Result<Integrity, io::Error> {
+        let path = match (self.integrity.as_ref(), self.streamed_length()) {
+            (Some(integrity), _) => return Ok(integrity.clone()),
+            (None, Some((path, _))) => path,
+            (None, None) => return Ok(Integrity::from_data(self.data.as_slice())),
+        };
+
+        let cache = || self.streamed_integrity.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(integrity) = cache().as_ref() {
+            return Ok(integrity.clone());
+        }
+
+        let integrity = Integrity::from_reader(fs::File::open(path)?)?;
+
+        let unchanged = match (self.stamp, path.metadata()) {
+            (Some(stamp), Ok(metadata)) => !stamp.is_changed(&metadata),
+            _ => false,
+        };
+
+        if unchanged {
+            *cache() = Some(integrity.clone());
+        }
+
+        Ok(integrity)
+    }
+
+    /// Whether the file of the resource has been modified, which is decided in the same way as `reload_if_needed`.
+    #[inline]
+    fn is_modified(&self, options: Options) -> bool {
+        let path = match self.path.as_deref() {
+            Some(path) => path,
+            None => return false,
+        };
+
+        if self.error.is_some() {
+            return true;
+        }
+
+        match path.metadata() {
+            Ok(metadata) => self.is_changed(path, &metadata, options).unwrap_or(true),
+            Err(_) => true,
+        }
+    }
+
+    /// Whether the file has been changed according to its metadata (or its content, if `options.content_hash` is `true` and the file is not streamed).
+    fn is_changed(
+        &self,
+        path: &Path,
+        metadata: &Metadata,
+        options: Options,
+    ) -> Result<bool, io::Error> {
+        // the file watcher has noticed a change, which may be finer than the precision of mtime
+        if self.dirty || self.stamp.map_or(true, |stamp| stamp.is_changed(metadata)) {
+            return Ok(true);
+        }
+
+        if options.content_hash && self.streamed.is_none() {
+            let data = read_data(path, &self.mime)?;
+
+            return Ok(compute_data_etag(&data).strong_ne(&self.etag));
+        }
+
+        Ok(false)
+    }
+
+    /// Reload the resource if its file has been modified (or failed to be loaded last time). Returns whether it has been reloaded. The resource is replaced instead of being modified, so snapshots which share it are not affected.
+    ///
+    /// If the file cannot be loaded, the error is kept in the resource along with the last good content, and the file is tried again next time.
+    fn reload_if_needed(resource: &mut Arc<Resource>, options: Options) -> Result<bool, io::Error> {
+        let path = match resource.path.clone() {
+            Some(path) => path,
+            None => return Ok(false),
+        };
+
+        let result = path.metadata().and_then(|metadata| {
+            if resource.error.is_none() && !resource.is_changed(&path, &metadata, options)? {
+                return Ok(None);
+            }
+
+            Resource::from_file(
+                path.clone(),
+                resource.mime.clone(),
+                &metadata,
+                resource.directory,
+                options,
+            )
+            .map(Some)
+        });
+
+        match result {
+            Ok(Some(reloaded)) => {
+                if resource.error.is_some() {
+                    rocket::info!("{:?} has been loaded again.", path);
+                }
+
+                *resource = Arc::new(reloaded);
+
+                Ok(true)
+            },
+            Ok(None) => Ok(false),
+            Err(err) => {
+                let err = Arc::new(err);
+
+                let is_new_error =
+                    resource.error.as_ref().map(|e| e.to_string()) != Some(err.to_string());
+
+                if is_new_error {
+                    rocket::warn!(
+                        "Failed to load {:?}, so {}: {}",
+                        path,
+                        if resource.is_loaded() {
+                            "its last good content is served"
+                        } else {
+                            "an error page is served"
+                        },
+                        err
+                    );
+                }
+
+                // the resource is only replaced if the error is new, so that no snapshot is published for it
+                if is_new_error || resource.dirty {
+                    let resource = Arc::make_mut(resource);
+
+                    resource.error = Some(err.clone());
+                    resource.dirty = false;
+                }
+
+                Err(io::Error::new(err.kind(), err))
+            },
+        }
+    }
+}
+
+/// A directory whose files are registered as resources.
+#[derive(Debug)]
+struct Directory {
+    prefix:  String,
+    path:    PathBuf,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl Directory {
+    /// Find the files which should be registered, with their names.
+    fn scan(&self) -> Vec<(String, PathBuf)> {
+        let mut files = Vec::new();
+
+        collect_files(&self.path, &self.path, &mut files);
+
+        files.retain(|(relative_path, _)| is_matched(relative_path, &self.include, &self.exclude));
+
+        let is_sidecar = |relative_path: &str| match Encoding::split_sidecar_path(relative_path) {
+            Some((original_path, _)) => files.iter().any(|(r, _)| r == original_path),
+            None => false,
+        };
+
+        files
+            .iter()
+            .filter(|(relative_path, _)| !is_sidecar(relative_path))
+            .map(|(relative_path, path)| {
+                (format!("{}{}", self.prefix, relative_path), path.clone())
+            })
+            .collect()
+    }
+}
+
+/// An archive file whose files are registered as resources.
+#[cfg(feature = "archive")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Archive {
+    prefix: String,
+    path:   PathBuf,
+    stamp:  Option<FileStamp>,
+    dirty:  bool,
+    /// Whether the archive failed to be read last time.
+    failed: bool,
+}
+
+#[cfg(feature = "archive")]
+impl Archive {
+    #[inline]
+    fn is_changed(&self, metadata: &Metadata) -> bool {
+        self.stamp.map_or(true, |stamp| stamp.is_changed(metadata))
+    }
+
+    /// Whether the archive file has been changed (or failed to be read last time).
+    #[inline]
+    fn is_modified(&self) -> bool {
+        self.dirty
+            || self.failed
+            || match self.path.metadata() {
+                Ok(metadata) => self.is_changed(&metadata),
+                Err(_) => true,
+            }
+    }
+}
+
+type Resources = HashMap<Cow<'static, str>, Arc<Resource>>;
+
+#[derive(Debug)]
+struct Inner {
+    resources:      Resources,
+    directories:    Vec<Arc<Directory>>,
+    #[cfg(feature = "archive")]
+    archives:       Vec<Archive>,
+    errors:         Vec<(String, io::Error)>,
+    versions:       Versions,
+    #[cfg(feature = "watch")]
+    watcher:        Option<FileWatcher>,
+    options:        Options,
+    cache_policies: Vec<CachePolicyRule>,
+}
+
+impl Inner {
+    #[inline]
+    fn new() -> Inner {
+        Inner {
+            resources:                            HashMap::new(),
+            directories:                          Vec::new(),
+            #[cfg(feature = "archive")]
+            archives:                             Vec::new(),
+            errors:                               Vec::new(),
+            versions:                             Versions::default(),
+            #[cfg(feature = "watch")]
+            watcher:                              create_watcher(),
+            options:                              Options {
+                stream_threshold:                             DEFAULT_STREAM_THRESHOLD,
+                content_hash:                                 false,
+                #[cfg(feature = "integrity")]
+                digest_headers:                               DigestHeaders::new(),
+            },
+            cache_policies:                       Vec::new(),
+        }
+    }
+
+    #[inline]
+    fn register_resource_file(
+        &mut self,
+        name: &'static str,
+        file_path: PathBuf,
+    ) -> Result<(), io::Error> {
+        let file_path = absolute_path(file_path);
+
+        self.insert(Cow::Borrowed(name), file_path.clone(), None);
+
+        if let Some(parent) = file_path.parent() {
+            self.watch(parent, false);
+        }
+
+        Ok(())
+    }
+
+    fn register_resource_directory(
+        &mut self,
+        prefix: String,
+        directory_path: PathBuf,
+        include: &[&str],
+        exclude: &[&str],
+    ) -> Result<(), io::Error> {
+        let path = absolute_path(directory_path);
+
+        if !path.is_dir() {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("{:?} is not a directory.", path),
+            ));
+        }
+
+        let directory = Directory {
+            prefix,
+            path,
+            include: compile_patterns(include)?,
+            exclude: compile_patterns(exclude)?,
+        };
+
+        self.watch(&directory.path, true);
+
+        self.directories.push(Arc::new(directory));
+
+        self.scan_directory(self.directories.len() - 1);
+
+        Ok(())
+    }
+
+    #[inline]
+    fn unregister_resource_file(&mut self, name: &str) -> Option<PathBuf> {
+        self.remove_cache_policy(name);
+
+        self.resources.remove(name).and_then(|resource| resource.path.clone())
+    }
+
+    #[inline]
+    fn register_resource(&mut self, name: Cow<'static, str>, mime: Mime, data: ResourceData) {
+        self.insert_data(name, mime, data.to_vec(), Vec::new(), Some(SystemTime::now()), None);
+    }
+
+    /// Register a resource with its data instead of a file, so it is never reloaded by itself.
+    fn insert_data(
+        &mut self,
+        name: Cow<'static, str>,
+        mime: Mime,
+        data: Vec<u8>,
+        precompressed: Vec<(Encoding, Vec<u8>)>,
+        mtime: Option<SystemTime>,
+        archive: Option<usize>,
+    ) {
+        let data = Arc::new(prepare_data(data, &mime));
+
+        let etag = compute_data_etag(data.as_slice());
+
+        let encoded = Arc::new(EncodedResources::new(None, &mime, data.clone(), precompressed));
+
+        self.versions.record(&mime);
+
+        #[cfg(not(feature = "archive"))]
+        let _ = archive;
+
+        let resource = Resource {
+            #[cfg(feature = "integrity")]
+            integrity: Some(Integrity::from_data(data.as_slice())),
+            #[cfg(feature = "integrity")]
+            streamed_integrity: Arc::default(),
+            path: None,
+            mime,
+            data,
+            etag,
+            encoded,
+            mtime,
+            stamp: None,
+            streamed: None,
+            error: None,
+            directory: None,
+            #[cfg(feature = "archive")]
+            archive,
+            dirty: false,
+        };
+
+        self.resources.insert(name, Arc::new(resource));
+    }
+
+    #[cfg(feature = "archive")]
+    fn register_resource_archive(&mut self, prefix: &str, archive: &[u8]) -> Result<(), io::Error> {
+        let build_time = build_time();
+
+        for file in read_archive(archive)? {
+            let mime = mime_guess::from_path(&file.name).first_or_octet_stream();
+
+            self.insert_data(
+                Cow::Owned(format!("{}{}", prefix, file.name)),
+                mime,
+                file.data,
+                file.encoded,
+                Some(file.modified.unwrap_or(build_time)),
+                None,
+            );
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "archive")]
+    fn register_resource_archive_file(
+        &mut self,
+        prefix: String,
+        path: PathBuf,
+    ) -> Result<(), io::Error> {
+        let path = absolute_path(path);
+
+        self.archives.push(Archive {
+            prefix,
+            path: path.clone(),
+            stamp: None,
+            dirty: false,
+            failed: false,
+        });
+
+        if let Err(err) = self.load_archive(self.archives.len() - 1) {
+            self.archives.pop();
+
+            return Err(err);
+        }
+
+        if let Some(parent) = path.parent() {
+            self.watch(parent, false);
+        }
+
+        Ok(())
+    }
+
+    /// Read an archive file again and replace the resources of its files.
+    #[cfg(feature = "archive")]
+    fn load_archive(&mut self, index: usize) -> Result<(), io::Error> {
+        let path = &self.archives[index].path;
+
+        let metadata = path.metadata()?;
+
+        let mtime = metadata.modified().ok();
+
+        let files = read_archive(&fs::read(path)?)?;
+
+        let versions = &mut self.versions;
+
+        self.resources.retain(|_, resource| {
+            let retained = resource.archive != Some(index);
+
+            if !retained {
+                versions.record(&resource.mime);
+            }
+
+            retained
+        });
+
+        let prefix = self.archives[index].prefix.clone();
+
+        for file in files {
+            let mime = mime_guess::from_path(&file.name).first_or_octet_stream();
+
+            self.insert_data(
+                Cow::Owned(format!("{}{}", prefix, file.name)),
+                mime,
+                file.data,
+                file.encoded,
+                file.modified.or(mtime),
+                Some(index),
+            );
+        }
+
+        let archive = &mut self.archives[index];
+
+        archive.stamp = Some(FileStamp::new(&metadata));
+        archive.dirty = false;
+        archive.failed = false;
+
+        Ok(())
+    }
+
+    /// Read the archive files which have been changed again. With a file watcher, only the archive files which have been changed are checked. If an archive file cannot be read, the resources of its last good content are kept and it is tried again next time.
+    #[cfg(feature = "archive")]
+    fn check_archives(&mut self, watched: bool) {
+        for index in 0..self.archives.len() {
+            let archive = &self.archives[index];
+
+            if watched && !archive.dirty && !archive.failed {
+                continue;
+            }
+
+            if archive.is_modified() {
+                if let Err(err) = self.load_archive(index) {
+                    let archive = &mut self.archives[index];
+
+                    if !archive.failed {
+                        rocket::warn!(
+                            "Failed to read {:?}, so its last good content is served: {}",
+                            archive.path,
+                            err
+                        );
+                    }
+
+                    archive.dirty = false;
+                    archive.failed = true;
+                }
+            }
+        }
+    }
+
+    #[cfg(not(feature = "archive"))]
+    #[inline]
+    fn check_archives(&mut self, _watched: bool) {}
+
+    #[inline]
+    fn unregister_resource(&mut self, name: &str) -> bool {
+        self.remove_cache_policy(name);
+
+        match self.resources.remove(name) {
+            Some(resource) => {
+                self.versions.record(&resource.mime);
+
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Reload every resource which needs it. A file which cannot be loaded does not stop the others from being reloaded, and the first error is returned at the end.
+    fn reload_if_needed(&mut self) -> Result<(), io::Error> {
+        self.scan_directories();
+        self.check_archives(false);
+
+        let mut result = Ok(());
+
+        for resource in self.resources.values_mut() {
+            match Resource::reload_if_needed(resource, self.options) {
+                Ok(true) => self.versions.record(&resource.mime),
+                Ok(false) => (),
+                Err(err) => {
+                    if result.is_ok() {
+                        result = Err(err);
+                    }
+                },
+            }
+        }
+
+        result
+    }
+
+    /// Reload the specific resource if needed. Unknown names make the registered directories scanned again, in case the file has just been added.
+    ///
+    /// With a file watcher, only the files and directories which have been changed are checked. If the file cannot be loaded, the resource is kept with the error.
+    fn update(&mut self, name: &str) -> Result<(), Error> {
+        let watched = self.apply_changes();
+
+        self.check_archives(watched);
+
+        if !watched && !self.resources.contains_key(name) {
+            self.scan_directories();
+        }
+
+        let resource = match self.resources.get_mut(name) {
+            Some(resource) => resource,
+            None => return Err(Error::NotFound(name.to_string())),
+        };
+
+        if !watched || resource.dirty || resource.error.is_some() {
+            match Resource::reload_if_needed(resource, self.options) {
+                Ok(true) => self.versions.record(&resource.mime),
+                Ok(false) => (),
+                // the file has been removed from its directory
+                Err(err) if resource.directory.is_some() && err.kind() == ErrorKind::NotFound => {
+                    self.resources.remove(name);
+
+                    return Err(Error::NotFound(name.to_string()));
+                },
+                Err(_) => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Register a resource from a file. If the file cannot be loaded, it is registered without content and loaded once it is available.
+    fn insert(&mut self, name: Cow<'static, str>, path: PathBuf, directory: Option<usize>) {
+        let mime = match path.extension() {
+            Some(extension) => match extension.to_str() {
+                Some(extension) => mime_guess::from_ext(extension).first_or_octet_stream(),
+                None => mime::APPLICATION_OCTET_STREAM,
+            },
+            None => mime::APPLICATION_OCTET_STREAM,
+        };
+
+        let resource = match path.metadata().and_then(|metadata| {
+            Resource::from_file(path.clone(), mime.clone(), &metadata, directory, self.options)
+        }) {
+            Ok(resource) => resource,
+            Err(err) => {
+                rocket::warn!(
+                    "Failed to load {:?}, so an error page is served until it can be loaded: {}",
+                    path,
+                    err
+                );
+
+                Resource::failed(path, mime, directory, err)
+            },
+        };
+
+        self.resources.insert(name, Arc::new(resource));
+    }
+
+    #[cfg(feature = "live-reload")]
+    fn poll_changes(&mut self) -> Versions {
+        let watched = self.apply_changes();
+
+        if !watched {
+            self.scan_directories();
+        }
+
+        self.check_archives(watched);
+
+        for resource in self.resources.values_mut() {
+            if (!watched || resource.dirty || resource.error.is_some())
+                && Resource::reload_if_needed(resource, self.options).unwrap_or(false)
+            {
+                self.versions.record(&resource.mime);
+            }
+        }
+
+        self.versions
+    }
+
+    /// Watch a directory if there is a file watcher. If it fails, the file watcher is dropped and files are checked on every request instead.
+    #[cfg(feature = "watch")]
+    fn watch(&mut self, path: &Path, recursive: bool) {
+        if let Some(watcher) = self.watcher.as_mut() {
+            if let Err(err) = watcher.watch(path, recursive) {
+                rocket::warn!(
+                    "Failed to watch {:?}, so files are checked on every request: {}",
+                    path,
+                    err
+                );
+
+                self.watcher = None;
+            }
+        }
+    }
+
+    #[cfg(not(feature = "watch"))]
+    #[inline]
+    fn watch(&mut self, _path: &Path, _recursive: bool) {}
+
+    /// Mark the resources whose files (or sidecar files) have been changed as dirty and scan the changed directories again. Returns whether there is a file watcher.
+    #[cfg(feature = "watch")]
+    fn apply_changes(&mut self) -> bool {
+        let changes = match self.watcher.as_ref() {
+            Some(watcher) => watcher.take_changes(),
+            None => return false,
+        };
+
+        match changes {
+            Some(Changes::Paths(paths)) => {
+                for resource in self.resources.values_mut() {
+                    if let Some(path) = resource.path.as_deref() {
+                        if paths.contains(path)
+                            || Encoding::ALL
+                                .iter()
+                                .any(|&encoding| paths.contains(&encoding.sidecar_path(path)))
+                        {
+                            Arc::make_mut(resource).dirty = true;
+                        }
+                    }
+                }
+
+                for index in 0..self.directories.len() {
+                    let directory_path = &self.directories[index].path;
+
+                    if paths.iter().any(|path| path.starts_with(directory_path)) {
+                        self.scan_directory(index);
+                    }
+                }
+
+                #[cfg(feature = "archive")]
+                for archive in self.archives.iter_mut() {
+                    if paths.contains(&archive.path) {
+                        archive.dirty = true;
+                    }
+                }
+            },
+            Some(Changes::All) => {
+                for resource in self.resources.values_mut() {
+                    Arc::make_mut(resource).dirty = true;
+                }
+
+                #[cfg(feature = "archive")]
+                for archive in self.archives.iter_mut() {
+                    archive.dirty = true;
+                }
+
+                self.scan_directories();
+            },
+            None => (),
+        }
+
+        true
+    }
+
+    #[cfg(not(feature = "watch"))]
+    #[inline]
+    fn apply_changes(&mut self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn scan_directories(&mut self) {
+        for index in 0..self.directories.len() {
+            self.scan_directory(index);
+        }
+    }
+
+    /// Register the new files of a directory and unregister the removed ones.
+    fn scan_directory(&mut self, index: usize) {
+        let files = self.directories[index].scan();
+
+        let versions = &mut self.versions;
+
+        self.resources.retain(|name, resource| {
+            let retained =
+                resource.directory != Some(index) || files.iter().any(|(n, _)| n == name);
+
+            if !retained {
+                versions.record(&resource.mime);
+            }
+
+            retained
+        });
+
+        for (name, path) in files {
+            if !self.resources.contains_key(name.as_str()) {
+                self.insert(Cow::Owned(name.clone()), path, Some(index));
+
+                let mime = self.resources[name.as_str()].mime.clone();
+
+                self.versions.record(&mime);
+            }
+        }
+    }
+
+    /// Set the `Cache-Control` policy of a name, or of every name starting with a prefix. The rule set last takes precedence.
+    #[inline]
+    fn set_cache_policy(&mut self, name: &str, prefix: bool, cache_policy: CachePolicy) {
+        self.cache_policies.retain(|rule| rule.prefix != prefix || rule.name != name);
+
+        self.cache_policies.push(CachePolicyRule {
+            name: name.to_string(),
+            prefix,
+            cache_policy,
+        });
+    }
+
+    /// Forget the `Cache-Control` policy of an unregistered name, like in the release profile. Policies of prefixes are kept.
+    #[inline]
+    fn remove_cache_policy(&mut self, name: &str) {
+        self.cache_policies.retain(|rule| rule.prefix || rule.name != name);
+    }
+
+    /// Take a snapshot of the resources for readers.
+    #[inline]
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            resources:                                   self.resources.clone(),
+            #[cfg(feature = "archive")]
+            archives:                                    self.archives.clone(),
+            #[cfg(feature = "live-reload")]
+            directories:                                 self.directories.clone(),
+            #[cfg(feature = "live-reload")]
+            versions:                                    self.versions,
+            options:                                     self.options,
+            cache_policies:                              self.cache_policies.clone(),
+            #[cfg(feature = "watch")]
+            watcher:                                     self
+                .watcher
+                .as_ref()
+                .map(FileWatcher::state),
+        }
+    }
+
+    /// Whether `snapshot` still shows the resources as they are, so that publishing a new one can be skipped. Resources are replaced instead of being modified, so comparing their pointers is enough.
+    fn is_published(&self, snapshot: &Snapshot) -> bool {
+        #[cfg(feature = "archive")]
+        if self.archives != snapshot.archives {
+            return false;
+        }
+
+        #[cfg(feature = "watch")]
+        if self.watcher.is_some() != snapshot.watcher.is_some() {
+            return false;
+        }
+
+        #[cfg(feature = "live-reload")]
+        if self.versions != snapshot.versions
+            || self.directories.len() != snapshot.directories.len()
+        {
+            return false;
+        }
+
+        self.options == snapshot.options
+            && self.cache_policies == snapshot.cache_policies
+            && self.resources.len() == snapshot.resources.len()
+            && self.resources.iter().all(|(name, resource)| {
+                snapshot.resources.get(name).map_or(false, |r| Arc::ptr_eq(resource, r))
+            })
+    }
+}
+
+/// The resources published for readers. A new snapshot is published after every change, so reading resources never takes the lock of `Inner`, even while files are being reloaded.
+#[derive(Debug)]
+struct Snapshot {
+    resources:      Resources,
+    #[cfg(feature = "archive")]
+    archives:       Vec<Archive>,
+    #[cfg(feature = "live-reload")]
+    directories:    Vec<Arc<Directory>>,
+    #[cfg(feature = "live-reload")]
+    versions:       Versions,
+    options:        Options,
+    cache_policies: Vec<CachePolicyRule>,
+    #[cfg(feature = "watch")]
+    watcher:        Option<Arc<WatcherState>>,
+}
+
+impl Snapshot {
+    /// Get the `Cache-Control` policy of the specific resource, which is empty if none has been set.
+    #[inline]
+    fn cache_policy(&self, name: &str) -> CachePolicy {
+        self.cache_policies
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(name))
+            .map(|rule| rule.cache_policy)
+            .unwrap_or_default()
+    }
+
+    /// Whether the specific resource can be read without reloading anything, which is only known without I/O if there is a file watcher.
+    #[cfg(feature = "watch")]
+    #[inline]
+    fn is_fresh(&self, name: &str) -> bool {
+        match (self.watcher.as_deref(), self.resources.get(name)) {
+            (Some(watcher), Some(resource)) => {
+                !watcher.has_changes() && !resource.dirty && resource.error.is_none()
+            },
+            _ => false,
+        }
+    }
+
+    #[cfg(not(feature = "watch"))]
+    #[inline]
+    fn is_fresh(&self, _name: &str) -> bool {
+        false
+    }
+
+    /// Whether the specific resource can be read without reloading anything. With a file watcher, it only costs an atomic operation. Otherwise, the metadata of its file (and of the archive files) is checked, which blocks the current thread but needs no lock.
+    fn is_unmodified(&self, name: &str) -> bool {
+        let resource = match self.resources.get(name) {
+            Some(resource) => resource,
+            None => return false,
+        };
+
+        if self.is_fresh(name) {
+            return true;
+        }
+
+        #[cfg(feature = "watch")]
+        if self.watcher.is_some() {
+            return false;
+        }
+
+        !resource.is_modified(self.options) && !self.is_any_archive_modified()
+    }
+
+    /// Whether anything needs to be reloaded or registered, which is decided in the same way as `Inner::poll_changes`. With a file watcher, it only costs a few atomic operations. Otherwise, the metadata of every file is checked and the directories are scanned, which blocks the current thread but needs no lock.
+    #[cfg(feature = "live-reload")]
+    fn is_any_modified(&self) -> bool {
+        #[cfg(feature = "watch")]
+        if let Some(watcher) = self.watcher.as_deref() {
+            #[cfg(feature = "archive")]
+            if self.archives.iter().any(|archive| archive.dirty || archive.failed) {
+                return true;
+            }
+
+            return watcher.has_changes()
+                || self
+                    .resources
+                    .values()
+                    .any(|resource| resource.dirty || resource.error.is_some());
+        }
+
+        self.is_any_archive_modified()
+            || self.resources.values().any(|resource| resource.is_modified(self.options))
+            || (0..self.directories.len()).any(|index| self.is_directory_modified(index))
+    }
+
+    /// Whether files have been added to or removed from a directory, which is decided in the same way as `Inner::scan_directory`.
+    #[cfg(feature = "live-reload")]
+    fn is_directory_modified(&self, index: usize) -> bool {
+        let files = self.directories[index].scan();
+
+        files.iter().any(|(name, _)| !self.resources.contains_key(name.as_str()))
+            || self.resources.iter().any(|(name, resource)| {
+                resource.directory == Some(index) && !files.iter().any(|(n, _)| n == name)
+            })
+    }
+
+    /// Whether any archive file has been changed.
+    #[cfg(feature = "archive")]
+    #[inline]
+    fn is_any_archive_modified(&self) -> bool {
+        self.archives.iter().any(Archive::is_modified)
+    }
+
+    #[cfg(not(feature = "archive"))]
+    #[inline]
+    fn is_any_archive_modified(&self) -> bool {
+        false
+    }
+}
 
+/// The resources and the lock which serializes their changes.
 #[derive(Debug)]
-struct Resource {
-    path:  PathBuf,
-    // mime could be an atom `Mime`, so just clone it
-    mime:  Mime,
-    data:  Arc<Vec<u8>>,
-    etag:  EntityTag<'static>,
-    mtime: Option<SystemTime>,
+struct Shared {
+    inner:    Mutex<Inner>,
+    snapshot: ArcSwap<Snapshot>,
+}
+
+impl Shared {
+    /// Change the resources, then publish a new snapshot if anything has changed.
+    #[inline]
+    fn modify<T>(&self, f: impl FnOnce(&mut Inner) -> T) -> T {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let result = f(&mut inner);
+
+        if !inner.is_published(&self.snapshot.load()) {
+            self.snapshot.store(Arc::new(inner.snapshot()));
+        }
+
+        result
+    }
+
+    #[inline]
+    fn snapshot(&self) -> Arc<Snapshot> {
+        self.snapshot.load_full()
+    }
+
+    /// Reload the specific resource if needed, which blocks the current thread. Whether it has been modified is checked against the snapshot without any lock, so requests for unchanged resources never wait for a reload. The lock is only taken to reload something.
+    fn refresh(&self, name: &str) -> Result<(), Error> {
+        if self.snapshot().is_unmodified(name) {
+            return Ok(());
+        }
+
+        self.modify(|inner| inner.update(name))
+    }
+
+    /// Reload the changed resources for live reloading, then return the counts of changes. Whether anything has changed is checked against the snapshot without any lock first, so polling only takes the lock when something needs to be reloaded.
+    #[cfg(feature = "live-reload")]
+    fn poll_changes(&self) -> Versions {
+        let snapshot = self.snapshot();
+
+        if snapshot.is_any_modified() {
+            self.modify(Inner::poll_changes)
+        } else {
+            snapshot.versions
+        }
+    }
+
+    /// Get the specific resource from the current snapshot, without reloading anything.
+    #[inline]
+    fn get(&self, name: &str) -> Result<Arc<Resource>, Error> {
+        self.snapshot()
+            .resources
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(name.to_string()))
+    }
 }
 
+/// Reloadable file resources. Changes are serialized by a lock, and readers get an immutable snapshot which is replaced after every change, so they can be reloaded through a shared reference like `StaticResources` is read.
 #[derive(Debug)]
-/// Reloadable file resources.
 pub struct FileResources {
-    resources: HashMap<&'static str, Resource>,
+    shared: Arc<Shared>,
 }
 
 impl FileResources {
     /// Create an instance of `FileResources`.
     #[inline]
     pub fn new() -> FileResources {
+        let inner = Inner::new();
+
+        let snapshot = ArcSwap::from_pointee(inner.snapshot());
+
         FileResources {
-            resources: HashMap::new()
+            shared: Arc::new(Shared {
+                inner: Mutex::new(inner),
+                snapshot,
+            }),
         }
     }
 
-    /// Register a resource from a path and it can be reloaded automatically.
+    #[inline]
+    fn modify<T>(&self, f: impl FnOnce(&mut Inner) -> T) -> T {
+        self.shared.modify(f)
+    }
+
+    /// Report that the resource (or the directory) named `name` failed to be registered. The fairing fails to ignite if any error is reported.
+    #[inline]
+    pub fn report_error<S: Into<String>>(&mut self, name: S, error: io::Error) {
+        self.modify(|inner| inner.errors.push((name.into(), error)));
+    }
+
+    /// Take the reported errors.
+    #[inline]
+    pub(crate) fn take_errors(&mut self) -> Vec<(String, io::Error)> {
+        self.modify(|inner| std::mem::take(&mut inner.errors))
+    }
+
+    /// Register a resource from a path and it can be reloaded automatically. If the file cannot be loaded yet, an error page is served until it can. Precompressed sidecar files next to it (`.br`, `.zst` and `.gz`) are used for encoded responses. Otherwise, if the data is compressible, it is compressed on demand with every enabled encoding.
     #[inline]
     pub fn register_resource_file<P: Into<PathBuf>>(
         &mut self,
         name: &'static str,
         file_path: P,
     ) -> Result<(), io::Error> {
-        let path = file_path.into();
+        self.modify(|inner| inner.register_resource_file(name, file_path.into()))
+    }
 
-        let metadata = path.metadata()?;
+    /// Register the files in a directory as resources which can be reloaded automatically. Each file is named by `prefix` followed by its relative path (separated by `/`). Only files matched by any `include` glob pattern (if there is any) and not matched by any `exclude` glob pattern are registered. Files added to or removed from the directory are picked up while the server is running. Sidecar files (e.g. **app.js.br** next to **app.js**) are used as precompressed data instead of resources.
+    #[inline]
+    pub fn register_resource_directory<S: Into<String>, P: Into<PathBuf>>(
+        &mut self,
+        prefix: S,
+        directory_path: P,
+        include: &[&str],
+        exclude: &[&str],
+    ) -> Result<(), io::Error> {
+        self.modify(|inner| {
+            inner.register_resource_directory(
+                prefix.into(),
+                directory_path.into(),
+                include,
+                exclude,
+            )
+        })
+    }
 
-        let mtime = metadata.modified().ok();
+    /// Unregister a resource from a file by a name.
+    #[inline]
+    pub fn unregister_resource_file<S: AsRef<str>>(&mut self, name: S) -> Option<PathBuf> {
+        self.modify(|inner| inner.unregister_resource_file(name.as_ref()))
+    }
 
-        let data = fs::read(&path)?;
+    /// Register a static resource with its data, like in the release profile. It is never reloaded, and its encoded variants are compressed on demand. Its `Last-Modified` time is `last_modified`.
+    #[inline]
+    pub fn register_resource_static(
+        &mut self,
+        name: &'static str,
+        mime: Mime,
+        data: &'static [u8],
+    ) {
+        self.modify(|inner| {
+            inner.insert_data(
+                Cow::Borrowed(name),
+                mime,
+                data.to_vec(),
+                Vec::new(),
+                Some(build_time()),
+                None,
+            )
+        });
+    }
 
-        let etag = compute_data_etag(&data);
+    /// Register a static resource embedded by `embed_file!`, like in the release profile. Its encoded variants are compressed on demand instead of using the embedded ones.
+    #[doc(hidden)]
+    #[inline]
+    pub fn register_resource_embedded(
+        &mut self,
+        name: &'static str,
+        mime: Mime,
+        (data, _, _, modified): EmbeddedFile,
+    ) {
+        let mtime = embedded_last_modified(modified, build_time());
 
-        let mime = match path.extension() {
-            Some(extension) => match extension.to_str() {
-                Some(extension) => mime_guess::from_ext(extension).first_or_octet_stream(),
-                None => mime::APPLICATION_OCTET_STREAM,
-            },
-            None => mime::APPLICATION_OCTET_STREAM,
-        };
+        self.modify(|inner| {
+            inner.insert_data(
+                Cow::Borrowed(name),
+                mime,
+                data.to_vec(),
+                Vec::new(),
+                Some(mtime),
+                None,
+            )
+        });
+    }
 
-        let resource = Resource {
-            path,
-            mime,
-            data: Arc::new(data),
-            etag,
-            mtime,
-        };
+    /// Register static resources embedded from a directory by `embed_directory!`, like in the release profile. Each file is named by `prefix` followed by its relative path, and its MIME type is guessed from the path. They are never reloaded, and their encoded variants are compressed on demand instead of using the embedded ones.
+    pub fn register_resource_directory_static<S: AsRef<str>>(
+        &mut self,
+        prefix: S,
+        files: &[EmbeddedDirectoryFile],
+    ) {
+        let prefix = prefix.as_ref();
 
-        self.resources.insert(name, resource);
+        let build_time = build_time();
 
-        Ok(())
+        self.modify(|inner| {
+            for &(relative_path, data, _, _, modified) in files {
+                let mime = mime_guess::from_path(relative_path).first_or_octet_stream();
+
+                inner.insert_data(
+                    Cow::Owned(format!("{}{}", prefix, relative_path)),
+                    mime,
+                    data.to_vec(),
+                    Vec::new(),
+                    Some(embedded_last_modified(modified, build_time)),
+                    None,
+                );
+            }
+        });
     }
 
-    /// Unregister a resource from a file by a name.
+    /// Encoded variants of resources registered with their data are compressed on demand in the debug profile, so the precompressed data is not used. Returns `false` if the name is not registered, like in the release profile.
     #[inline]
-    pub fn unregister_resource_file<S: AsRef<str>>(&mut self, name: S) -> Option<PathBuf> {
+    pub fn register_resource_static_encoded<S: AsRef<str>>(
+        &mut self,
+        name: S,
+        _encoding: Encoding,
+        _data: &'static [u8],
+    ) -> bool {
+        self.contains(name.as_ref())
+    }
+
+    /// Set the `Cache-Control` policy of a registered resource. It is kept when the file is reloaded. Returns `false` if the name is not registered.
+    #[inline]
+    pub fn set_cache_policy<S: AsRef<str>>(&mut self, name: S, cache_policy: CachePolicy) -> bool {
         let name = name.as_ref();
 
-        self.resources.remove(name).map(|resource| resource.path)
+        self.modify(|inner| {
+            if inner.resources.contains_key(name) {
+                inner.set_cache_policy(name, false, cache_policy);
+
+                true
+            } else {
+                false
+            }
+        })
     }
 
-    /// Reload resources if needed.
+    /// Set the `Cache-Control` policy of every resource whose name starts with `prefix`, such as the files registered by `register_resource_directory`. Files which appear in the directory later get the policy as well.
     #[inline]
-    pub fn reload_if_needed(&mut self) -> Result<(), io::Error> {
-        for resource in self.resources.values_mut() {
-            let metadata = resource.path.metadata()?;
+    pub fn set_directory_cache_policy<S: AsRef<str>>(
+        &mut self,
+        prefix: S,
+        cache_policy: CachePolicy,
+    ) {
+        self.modify(|inner| inner.set_cache_policy(prefix.as_ref(), true, cache_policy));
+    }
 
-            let (reload, new_mtime) = match resource.mtime {
-                Some(mtime) => match metadata.modified() {
-                    Ok(new_mtime) => (new_mtime > mtime, Some(new_mtime)),
-                    Err(_) => (true, None),
-                },
-                None => match metadata.modified() {
-                    Ok(new_mtime) => (true, Some(new_mtime)),
-                    Err(_) => (true, None),
-                },
-            };
+    #[inline]
+    pub(crate) fn cache_policy(&self, name: &str) -> CachePolicy {
+        self.shared.snapshot().cache_policy(name)
+    }
 
-            if reload {
-                let new_data = fs::read(&resource.path)?;
+    /// Register a resource with its data, such as data generated at startup. It is never reloaded. If the name is registered, the resource is replaced.
+    ///
+    /// It can be called at any time, e.g. through `StaticContextManager::resources` after the server is launched.
+    #[inline]
+    pub fn register_resource<S: Into<Cow<'static, str>>, D: Into<ResourceData>>(
+        &self,
+        name: S,
+        mime: Mime,
+        data: D,
+    ) {
+        self.modify(|inner| inner.register_resource(name.into(), mime, data.into()));
+    }
 
-                let new_etag = compute_data_etag(&new_data);
+    /// Unregister a resource by a name. Returns `false` if the name is not registered. Like `register_resource`, it can be called at any time.
+    #[inline]
+    pub fn unregister_resource<S: AsRef<str>>(&self, name: S) -> bool {
+        self.modify(|inner| inner.unregister_resource(name.as_ref()))
+    }
 
-                resource.data = Arc::new(new_data);
+    /// Register the files in a zip, tar or tar.gz archive (e.g. embedded by `include_bytes!`) as resources. Each file is named by `prefix` followed by its path in the archive (separated by `/`), and its MIME type is guessed from the path. The resources are never reloaded.
+    #[cfg(feature = "archive")]
+    #[inline]
+    pub fn register_resource_archive<S: AsRef<str>>(
+        &mut self,
+        prefix: S,
+        archive: &[u8],
+    ) -> Result<(), io::Error> {
+        self.modify(|inner| inner.register_resource_archive(prefix.as_ref(), archive))
+    }
 
-                resource.etag = new_etag;
+    /// Register the files in a zip, tar or tar.gz archive file as resources. Each file is named by `prefix` followed by its path in the archive (separated by `/`), and its MIME type is guessed from the path. The archive is read again whenever its modification time changes.
+    #[cfg(feature = "archive")]
+    #[inline]
+    pub fn register_resource_archive_file<S: AsRef<str>, P: AsRef<Path>>(
+        &mut self,
+        prefix: S,
+        archive_path: P,
+    ) -> Result<(), io::Error> {
+        self.modify(|inner| {
+            inner.register_resource_archive_file(
+                prefix.as_ref().to_string(),
+                archive_path.as_ref().to_path_buf(),
+            )
+        })
+    }
 
-                resource.mtime = new_mtime;
-            }
-        }
+    /// Set the size in bytes above which files are streamed from the disk on every request instead of being read into memory (16 MiB by default). Streamed resources have ETags derived from the lengths and the modification times of their files, and they are never compressed or injected with the live reload script. It applies to the files registered or reloaded afterward.
+    #[inline]
+    pub fn set_stream_threshold(&mut self, threshold: u64) {
+        self.modify(|inner| inner.options.stream_threshold = threshold);
+    }
 
-        Ok(())
+    /// Compare the content of files whose metadata has not changed as well, for file systems whose timestamps are too coarse to notice every change. Files are read on every request, so it is disabled by default. It does not apply to streamed files, nor when the `watch` feature is used, since the file watcher notices every change.
+    #[inline]
+    pub fn set_content_hash_check(&mut self, enabled: bool) {
+        self.modify(|inner| inner.options.content_hash = enabled);
     }
 
-    #[allow(clippy::type_complexity)]
-    /// Get the specific resource.
+    /// Set which digest headers (`Repr-Digest` and `Content-Digest`) are sent with the resources. No digest headers are sent by default. They are not sent with streamed files, whose digests are not computed.
+    #[cfg(feature = "integrity")]
     #[inline]
-    pub fn get_resource<S: AsRef<str>>(
-        &mut self,
-        name: S,
-    ) -> Result<(Mime, Arc<Vec<u8>>, &EntityTag<'static>), io::Error> {
-        let name = name.as_ref();
+    pub fn set_digest_headers(&mut self, digest_headers: DigestHeaders) {
+        self.modify(|inner| inner.options.digest_headers = digest_headers);
+    }
 
-        let resource = self.resources.get_mut(name).ok_or_else(|| {
-            io::Error::new(ErrorKind::NotFound, format!("The name `{}` is not found.", name))
-        })?;
+    #[cfg(feature = "integrity")]
+    #[inline]
+    pub(crate) fn digest_headers(&self) -> DigestHeaders {
+        self.shared.snapshot().options.digest_headers
+    }
 
-        let metadata = resource.path.metadata()?;
+    /// Override directories only take effect in the **release** profile, where resources are embedded, so this does nothing.
+    #[inline]
+    pub fn set_override_directory<P: Into<PathBuf>>(&mut self, _directory: P) {}
 
-        let (reload, new_mtime) = match resource.mtime {
-            Some(mtime) => match metadata.modified() {
-                Ok(new_mtime) => (new_mtime > mtime, Some(new_mtime)),
-                Err(_) => (true, None),
-            },
-            None => match metadata.modified() {
-                Ok(new_mtime) => (true, Some(new_mtime)),
-                Err(_) => (true, None),
-            },
-        };
+    /// Override directories only take effect in the **release** profile, so there is none and this returns `None`.
+    #[inline]
+    pub fn override_directory(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Resources are read from their files in the debug profile, so there is no override directory to read again. Returns `Ok(0)`.
+    #[inline]
+    pub fn rescan_overrides(&self) -> Result<usize, io::Error> {
+        Ok(0)
+    }
+
+    /// Get the time this crate was built, which is the `Last-Modified` time of the resources registered by `register_resource_static`, like in the release profile. Embedded files have their modification times instead, resources read from files and archive files have the modification times of the files, and resources registered by `register_resource` have the time they are registered.
+    #[inline]
+    pub fn last_modified(&self) -> SystemTime {
+        build_time()
+    }
+
+    /// Reload resources if needed. The registered directories are scanned again as well.
+    #[inline]
+    pub fn reload_if_needed(&self) -> Result<(), io::Error> {
+        self.modify(Inner::reload_if_needed)
+    }
+
+    /// Get the specific resource, reloading it if needed, and pass it to `f`. Checking and reloading block the current thread.
+    #[inline]
+    pub(crate) fn with_resource<T>(
+        &self,
+        name: &str,
+        f: impl FnOnce(&Resource) -> T,
+    ) -> Result<T, Error> {
+        if !self.shared.snapshot().is_fresh(name) {
+            self.shared.refresh(name)?;
+        }
+
+        self.shared.get(name).map(|resource| f(&resource))
+    }
 
-        if reload {
-            let new_data = fs::read(&resource.path)?;
+    /// Like `with_resource`, but checking and reloading run on a blocking thread of the runtime, so the executor is never stalled. With a file watcher, fresh resources are read from the snapshot without leaving the current task.
+    pub(crate) async fn with_resource_async<T>(
+        &self,
+        name: &str,
+        f: impl FnOnce(&Resource) -> T,
+    ) -> Result<T, Error> {
+        if !self.shared.snapshot().is_fresh(name) {
+            let shared = self.shared.clone();
+            let name = name.to_string();
 
-            let new_etag = compute_data_etag(&new_data);
+            task::spawn_blocking(move || shared.refresh(&name))
+                .await
+                .map_err(|err| Error::IOError(io::Error::new(ErrorKind::Other, err)))??;
+        }
+
+        self.shared.get(name).map(|resource| f(&resource))
+    }
+
+    /// Get a resource like `get_resource`, but checking and reloading it, and reading the file of a streamed resource, run on a blocking thread of the runtime.
+    pub(crate) async fn get_resource_async(&self, name: &str) -> Result<ResourceView, Error> {
+        let (mut view, streamed) = self
+            .with_resource_async(name, |resource| {
+                resource.check_loaded()?;
+
+                let streamed = resource.streamed_length().map(|(path, _)| path.to_path_buf());
+
+                Ok::<_, Error>((resource.view(resource.data.clone()), streamed))
+            })
+            .await??;
+
+        if let Some(path) = streamed {
+            let data = task::spawn_blocking(move || fs::read(path))
+                .await
+                .map_err(|err| Error::IOError(io::Error::new(ErrorKind::Other, err)))??;
+
+            view.data = ResourceData::Shared(Arc::new(data));
+        }
 
-            resource.data = Arc::new(new_data);
+        Ok(view)
+    }
 
-            resource.etag = new_etag;
+    /// Whether the name is registered, without reloading anything.
+    #[inline]
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.shared.snapshot().resources.contains_key(name)
+    }
 
-            resource.mtime = new_mtime;
+    /// Get another handle of the same resources.
+    #[cfg(any(feature = "live-reload", feature = "tera", feature = "handlebars"))]
+    #[inline]
+    pub(crate) fn share(&self) -> FileResources {
+        FileResources {
+            shared: self.shared.clone()
         }
+    }
+
+    /// Reload the changed resources and scan the directories again on a blocking thread, then return the counts of changes. It is polled by live reloading.
+    #[cfg(feature = "live-reload")]
+    pub(crate) async fn poll_changes(&self) -> Versions {
+        let shared = self.shared.clone();
 
-        Ok((resource.mime.clone(), resource.data.clone(), &resource.etag))
+        task::spawn_blocking(move || shared.poll_changes()).await.unwrap_or_default()
+    }
+}
+
+impl ResourceStore for FileResources {
+    #[inline]
+    fn get_resource(&self, name: &str) -> Result<ResourceView, Error> {
+        self.with_resource(name, |resource| {
+            resource.check_loaded()?;
+
+            Ok(resource.view(resource.read_data()?))
+        })?
+    }
+
+    #[inline]
+    fn get_resource_encoded(
+        &self,
+        name: &str,
+        encoding: Encoding,
+    ) -> Result<Option<ResourceView>, Error> {
+        self.with_resource(name, |resource| {
+            resource.check_loaded()?;
+
+            resource.encoded.load_all();
+
+            Ok(resource.encoded.get(encoding).map(|encoded| ResourceView {
+                mime:          resource.mime.clone(),
+                data:          ResourceData::Shared(encoded.data),
+                etag:          encoded.etag,
+                encoding:      Some(encoding),
+                last_modified: resource.mtime,
+            }))
+        })?
+    }
+
+    #[inline]
+    fn resource_names(&self) -> Vec<String> {
+        self.shared.snapshot().resources.keys().map(|name| name.to_string()).collect()
     }
 }
 
@@ -163,3 +1835,211 @@ impl Default for FileResources {
         FileResources::new()
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+    use crate::{
+        rocket::{self, get, http::Status, local::blocking::Client, routes},
+        StaticResponse,
+    };
+
+    crate::static_response_handler! {
+        "/file" => file => "file",
+    }
+
+    /// A directory for the files of a test, which is removed when it is dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!(
+                "rocket-include-static-resources-{}-{}",
+                std::process::id(),
+                name
+            ));
+
+            fs::create_dir_all(&path).unwrap();
+
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Serve the file at `path` as `/file`. The file watcher is dropped, so that changes are only noticed by the metadata of the file.
+    fn client(path: &Path) -> Client {
+        let path = path.to_path_buf();
+
+        let rocket = rocket::custom(rocket::Config::figment().merge(("log_level", "off")))
+            .attach(StaticResponse::fairing(move |resources| {
+                #[cfg(feature = "watch")]
+                resources.modify(|inner| inner.watcher = None);
+
+                resources.register_resource_file("file", path.clone()).unwrap();
+            }))
+            .mount("/", routes![file]);
+
+        Client::debug(rocket).unwrap()
+    }
+
+    /// Copy the modification time of `from` to `to`, so that a change of the other metadata can be checked alone.
+    fn copy_mtime(from: &Path, to: &Path) {
+        assert!(Command::new("touch").arg("-r").arg(from).arg(to).status().unwrap().success());
+    }
+
+    fn stamp(path: &Path) -> FileStamp {
+        FileStamp::new(&path.metadata().unwrap())
+    }
+
+    #[test]
+    fn a_change_of_the_length_alone_reloads_the_file() {
+        let dir = TempDir::new("length");
+        let (path, reference) = (dir.0.join("a.txt"), dir.0.join("reference"));
+
+        fs::write(&path, "first").unwrap();
+
+        let client = client(&path);
+
+        assert_eq!("first", client.get("/file").dispatch().into_string().unwrap());
+
+        let old_stamp = stamp(&path);
+
+        copy_mtime(&path, &reference);
+        fs::write(&path, "second").unwrap();
+        copy_mtime(&reference, &path);
+
+        let new_stamp = stamp(&path);
+
+        assert_eq!((old_stamp.mtime, old_stamp.inode), (new_stamp.mtime, new_stamp.inode));
+        assert_eq!("second", client.get("/file").dispatch().into_string().unwrap());
+    }
+
+    #[test]
+    fn a_change_of_the_inode_alone_reloads_the_file() {
+        let dir = TempDir::new("inode");
+        let (path, replacement) = (dir.0.join("a.txt"), dir.0.join("a.txt.tmp"));
+
+        fs::write(&path, "first").unwrap();
+
+        let client = client(&path);
+
+        assert_eq!("first", client.get("/file").dispatch().into_string().unwrap());
+
+        let old_stamp = stamp(&path);
+
+        fs::write(&replacement, "other").unwrap();
+        copy_mtime(&path, &replacement);
+        fs::rename(&replacement, &path).unwrap();
+
+        let new_stamp = stamp(&path);
+
+        assert_eq!((old_stamp.mtime, old_stamp.length), (new_stamp.mtime, new_stamp.length));
+        assert_eq!("other", client.get("/file").dispatch().into_string().unwrap());
+    }
+
+    #[test]
+    fn a_change_of_the_modification_time_alone_reloads_the_file() {
+        let dir = TempDir::new("mtime");
+        let path = dir.0.join("a.txt");
+
+        fs::write(&path, "first").unwrap();
+
+        let client = client(&path);
+
+        assert_eq!("first", client.get("/file").dispatch().into_string().unwrap());
+
+        let old_stamp = stamp(&path);
+
+        // the file is rewritten in place until its modification time differs, for file systems with coarse timestamps
+        let new_stamp = loop {
+            fs::write(&path, "other").unwrap();
+
+            let new_stamp = stamp(&path);
+
+            if new_stamp.mtime != old_stamp.mtime {
+                break new_stamp;
+            }
+
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+
+        assert_eq!((old_stamp.length, old_stamp.inode), (new_stamp.length, new_stamp.inode));
+        assert_eq!("other", client.get("/file").dispatch().into_string().unwrap());
+    }
+
+    #[test]
+    fn the_error_page_is_served_until_the_file_is_fixed() {
+        let dir = TempDir::new("error-page");
+        let path = dir.0.join("a.txt");
+
+        // a directory cannot be read as a file
+        fs::create_dir(&path).unwrap();
+
+        let client = client(&path);
+
+        let response = client.get("/file").dispatch();
+
+        assert_eq!(Status::InternalServerError, response.status());
+        assert!(response.into_string().unwrap().contains("a.txt"));
+
+        fs::remove_dir(&path).unwrap();
+
+        let response = client.get("/file").dispatch();
+
+        assert_eq!(Status::NotFound, response.status());
+        assert_eq!(Some("no-store"), response.headers().get_one("Cache-Control"));
+        assert!(response.into_string().unwrap().contains("a.txt"));
+
+        fs::write(&path, "fixed").unwrap();
+
+        let response = client.get("/file").dispatch();
+
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!("fixed", response.into_string().unwrap());
+    }
+
+    #[cfg(feature = "live-reload")]
+    #[test]
+    fn polling_unchanged_resources_does_not_take_the_lock() {
+        use std::{sync::mpsc, thread, time::Duration};
+
+        let dir = TempDir::new("poll");
+
+        fs::write(dir.0.join("a.txt"), "first").unwrap();
+
+        let mut resources = FileResources::new();
+
+        #[cfg(feature = "watch")]
+        resources.modify(|inner| inner.watcher = None);
+
+        resources.register_resource_directory("dir/", dir.0.clone(), &[], &[]).unwrap();
+
+        let shared = resources.shared.clone();
+
+        let versions = shared.poll_changes();
+
+        {
+            let _inner = shared.inner.lock().unwrap();
+
+            let (sender, receiver) = mpsc::channel();
+
+            let polling = shared.clone();
+
+            thread::spawn(move || sender.send(polling.poll_changes()).unwrap());
+
+            assert_eq!(Ok(versions), receiver.recv_timeout(Duration::from_secs(5)));
+        }
+
+        fs::write(dir.0.join("b.txt"), "second").unwrap();
+
+        assert_ne!(versions, shared.poll_changes());
+        assert!(resources.contains("dir/b.txt"));
+    }
+}
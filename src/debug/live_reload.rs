@@ -0,0 +1,206 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use super::{file_resources::Versions, FileResources};
+use crate::rocket::{
+    response::stream::{Event, EventStream},
+    tokio::{self, select, sync::watch, time},
+    Route, Shutdown, State,
+};
+
+/// The path of the endpoint which publishes change events as server-sent events.
+pub(crate) const LIVE_RELOAD_PATH: &str = "/__static-resources/live-reload";
+
+/// How often the resources are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The resources of every namespace, whose changes are published by the one endpoint of live reloading.
+/// The resources are polled by one task while any client is connected, and the counts of changes are published to every client through a channel. Polling unchanged resources takes no lock of the resources, so it never delays requests.
+/// The resources are polled by one task while any client is connected, and the counts of changes are published to every client through a channel.
+pub(crate) struct LiveReloadSources {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    sources:  Mutex<Vec<FileResources>>,
+    versions: watch::Sender<Vec<Versions>>,
+    /// Whether the polling task is running.
+    polling:  Mutex<bool>,
+}
+
+impl LiveReloadSources {
+    #[inline]
+    pub(crate) fn new(resources: FileResources) -> LiveReloadSources {
+        LiveReloadSources {
+            shared: Arc::new(Shared {
+                sources:  Mutex::new(vec![resources]),
+                versions: watch::channel(Vec::new()).0,
+                polling:  Mutex::new(false),
+            }),
+        }
+    }
+
+    /// Add the resources of another namespace.
+    #[inline]
+    pub(crate) fn push(&self, resources: FileResources) {
+        self.shared.sources.lock().unwrap_or_else(|err| err.into_inner()).push(resources);
+    }
+
+    /// Subscribe to the counts of changes of every namespace. The polling task is started if it is not running.
+    fn subscribe(&self) -> watch::Receiver<Vec<Versions>> {
+        let receiver = self.shared.versions.subscribe();
+
+        let mut polling = self.shared.polling.lock().unwrap_or_else(|err| err.into_inner());
+
+        if !*polling {
+            *polling = true;
+
+            tokio::spawn(self.shared.clone().poll());
+        }
+
+        receiver
+    }
+}
+
+impl Shared {
+    /// Poll the resources and publish their counts of changes when they change, until no client is subscribed.
+    async fn poll(self: Arc<Self>) {
+        let mut interval = time::interval(POLL_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            {
+                let mut polling = self.polling.lock().unwrap_or_else(|err| err.into_inner());
+
+                if self.versions.receiver_count() == 0 {
+                    *polling = false;
+
+                    return;
+                }
+            }
+
+            let versions = self.poll_changes().await;
+
+            self.versions.send_if_modified(|current| {
+                if *current != versions {
+                    *current = versions;
+
+                    true
+                } else {
+                    false
+                }
+            });
+        }
+    }
+
+    /// Reload the changed resources of every namespace and return their counts of changes.
+    async fn poll_changes(&self) -> Vec<Versions> {
+        let sources: Vec<FileResources> = self
+            .sources
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .iter()
+            .map(FileResources::share)
+            .collect();
+
+        let mut versions = Vec::with_capacity(sources.len());
+
+        for resources in sources {
+            versions.push(resources.poll_changes().await);
+        }
+
+        versions
+    }
+}
+
+/// Insert the client script before the last `</body>` of an HTML document, or append it if there is no `</body>`.
+///
+/// The script reloads the page when a resource changes, or only reloads the stylesheets if the changed resources are all CSS. It also reloads the page after reconnecting to a restarted server.
+pub(crate) fn inject_script(html: Vec<u8>) -> Vec<u8> {
+    let script = format!(
+        r#"<script>(function () {{
+    var source = new EventSource("{}");
+    var disconnected = false;
+    source.addEventListener("reload", function () {{
+        location.reload();
+    }});
+    source.addEventListener("css", function () {{
+        document.querySelectorAll('link[rel="stylesheet"]').forEach(function (link) {{
+            var url = new URL(link.href);
+            url.searchParams.set("live-reload", Date.now());
+            link.href = url.href;
+        }});
+    }});
+    source.onerror = function () {{
+        disconnected = true;
+    }};
+    source.onopen = function () {{
+        if (disconnected) location.reload();
+    }};
+}})();</script>
+"#,
+        LIVE_RELOAD_PATH
+    );
+
+    let index = html
+        .windows(7)
+        .rposition(|window| window.eq_ignore_ascii_case(b"</body>"))
+        .unwrap_or(html.len());
+
+    let mut injected = Vec::with_capacity(html.len() + script.len());
+
+    injected.extend_from_slice(&html[..index]);
+    injected.extend_from_slice(script.as_bytes());
+    injected.extend_from_slice(&html[index..]);
+
+    injected
+}
+
+#[rocket::get("/")]
+fn events(sources: &State<LiveReloadSources>, mut shutdown: Shutdown) -> EventStream![Event + '_] {
+    EventStream! {
+        let mut receiver = sources.subscribe();
+
+        let mut seen = receiver.borrow_and_update().clone();
+
+        loop {
+            select! {
+                changed = receiver.changed() => if changed.is_err() {
+                    break;
+                },
+                _ = &mut shutdown => break,
+            }
+
+            let versions = receiver.borrow_and_update().clone();
+
+            // the first counts, or the counts after another namespace is added
+            if versions.len() != seen.len() {
+                seen = versions;
+
+                continue;
+            }
+
+            // the counts of a namespace are compared with its own counts only
+            let changes = versions.iter().zip(seen.iter());
+
+            if changes.clone().any(|(versions, seen)| versions.changed > seen.changed) {
+                let reload = changes.into_iter().any(|(versions, seen)| versions.reloaded > seen.changed);
+
+                let event = if reload { "reload" } else { "css" };
+
+                seen = versions;
+
+                yield Event::empty().event(event);
+            }
+        }
+    }
+}
+
+/// The routes of live reloading, which should be mounted at `LIVE_RELOAD_PATH`.
+#[inline]
+pub(crate) fn routes() -> Vec<Route> {
+    rocket::routes![events]
+}
@@ -1,9 +1,35 @@
 /// Used in the fairing of `StaticResponse` to include static files into your executable binary file. You need to specify each file's name and its path relative to the directory containing the manifest of your package. In order to reduce the compilation time and allow to hot-reload resources, files are compiled into your executable binary file together, only when you are using the **release** profile.
+///
+/// A whole directory can be included by `prefix => dir(path, include = [globs], exclude = [globs])`, where `include` and `exclude` are optional and can be given in any order. Each file in it is named by the prefix followed by its relative path.
+///
+/// A zip, tar or tar.gz archive can be included by `prefix => archive(path)` with the `archive` feature. Each file in it is named by the prefix followed by its path in the archive, and the archive is read again whenever it changes.
+///
+/// Each entry can be followed by `with` and a `CachePolicy`.
 #[macro_export]
 macro_rules! static_resources_initialize {
-    ( $resources:expr, $($name:expr => $path:expr), * $(,)* ) => {
+    (@entry $resources:expr, $name:expr => dir($($directory:tt)*) $(with $cache_policy:expr)?) => {
+        let (directory_path, include, exclude) = $crate::directory_options!($($directory)*);
+
+        if let Err(err) = $resources.register_resource_directory($name, directory_path, include, exclude) {
+            $resources.report_error($name, err);
+        }
+        $($resources.set_directory_cache_policy($name, $cache_policy);)?
+    };
+    (@entry $resources:expr, $name:expr => archive($path:tt) $(with $cache_policy:expr)?) => {
+        if let Err(err) = $resources.register_resource_archive_file($name, $crate::manifest_dir_macros::not_directory_path!($path)) {
+            $resources.report_error($name, err);
+        }
+        $($resources.set_directory_cache_policy($name, $cache_policy);)?
+    };
+    (@entry $resources:expr, $name:expr => $path:tt $(with $cache_policy:expr)?) => {
+        if let Err(err) = $resources.register_resource_file($name, $crate::manifest_dir_macros::not_directory_path!($path)) {
+            $resources.report_error($name, err);
+        }
+        $($resources.set_cache_policy($name, $cache_policy);)?
+    };
+    ( $resources:expr, $($name:expr => $value:tt $(($($args:tt)*))? $(with $cache_policy:expr)?), * $(,)* ) => {
         $(
-            $resources.register_resource_file($name, $crate::manifest_dir_macros::not_directory_path!($path)).unwrap();
+            $crate::static_resources_initialize!(@entry $resources, $name => $value $(($($args)*))? $(with $cache_policy)?);
         )*
     };
 }
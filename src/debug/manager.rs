@@ -1,40 +1,174 @@
-use std::sync::{Mutex, PoisonError};
+use std::{io, marker::PhantomData};
 
 use super::FileResources;
-use crate::{EtagIfNoneMatch, StaticResponse};
+#[cfg(feature = "integrity")]
+use crate::Integrity;
+use crate::{
+    encoding::Encoding,
+    fingerprint::{fingerprint, fingerprinted_name},
+    Error, EtagIfNoneMatch, ResourceStore, ResourceView, StaticResponse,
+};
 
-/// To monitor the state of static resources.
+/// To monitor the state of static resources. `N` is the namespace of the resources (see `StaticResponseFairing::namespace`), so that independent sets of resources can be managed at the same time, such as `StaticContextManager<Admin>`.
 #[derive(Debug)]
-pub struct StaticContextManager {
-    pub resources: Mutex<FileResources>,
+pub struct StaticContextManager<N = ()> {
+    pub resources: FileResources,
+    namespace:     PhantomData<fn() -> N>,
 }
 
-impl StaticContextManager {
+impl<N> StaticContextManager<N> {
     #[inline]
-    pub(crate) fn new(resources: Mutex<FileResources>) -> StaticContextManager {
+    pub(crate) fn new(resources: FileResources) -> StaticContextManager<N> {
         StaticContextManager {
             resources,
+            namespace: PhantomData,
         }
     }
 
+    /// Build a `StaticResponse`. The response chooses the best encoded variant of the resource according to the `Accept-Encoding` header of the request.
+    ///
+    /// Returns `Err(Error::NotFound)` if the name is not registered. If the file cannot be loaded, the last good content is served, or a page which names the path and the error if there is none.
+    #[inline]
+    pub fn try_build<S: AsRef<str>>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        name: S,
+    ) -> Result<StaticResponse, Error> {
+        let response = self
+            .resources
+            .with_resource(name.as_ref(), |resource| {
+                StaticResponse::build(resource, etag_if_none_match)
+            })?
+            .with_cache_policy(self.resources.cache_policy(name.as_ref()))
+            .open();
+
+        #[cfg(feature = "integrity")]
+        let response = response.with_digest_headers(self.resources.digest_headers());
+
+        Ok(response)
+    }
+
+    /// Build a `StaticResponse` like `try_build`, but checking and reloading the resource run on a blocking thread of the runtime instead of stalling the executor. Concurrent requests for unchanged resources do not wait on each other.
+    #[inline]
+    pub async fn try_build_async<S: AsRef<str>>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        name: S,
+    ) -> Result<StaticResponse, Error> {
+        let response = self
+            .resources
+            .with_resource_async(name.as_ref(), |resource| {
+                StaticResponse::build(resource, etag_if_none_match)
+            })
+            .await?
+            .with_cache_policy(self.resources.cache_policy(name.as_ref()))
+            .open_async()
+            .await;
+
+        #[cfg(feature = "integrity")]
+        let response = response.with_digest_headers(self.resources.digest_headers());
+
+        Ok(response)
+    }
+
+    /// Get the data, MIME type and ETag of a resource without building a `StaticResponse`, such as to inline an SVG into a template or attach a logo to an email. The view is the same in the debug and release profiles, and stays valid even if the resource is reloaded later.
+    ///
+    /// It behaves like `ResourceStore::get_resource`, returning `Err(Error::NotFound)` if the name is not registered. Checking and reloading the resource run on a blocking thread of the runtime, so it should be preferred over `get_resource` in async handlers.
+    #[inline]
+    pub async fn get_resource_async<S: AsRef<str>>(&self, name: S) -> Result<ResourceView, Error> {
+        self.resources.get_resource_async(name.as_ref()).await
+    }
+
+    /// Get the digests of a resource, such as for the `integrity` attribute of `<script>` elements. They are computed whenever the file is loaded, except for streamed files, which are read to compute them here.
+    ///
+    /// Returns `Err(Error::NotFound)` if the name is not registered, or `Err(Error::IOError)` if the file cannot be loaded.
+    #[cfg(feature = "integrity")]
+    #[inline]
+    pub fn integrity<S: AsRef<str>>(&self, name: S) -> Result<Integrity, Error> {
+        self.resources.with_resource(name.as_ref(), |resource| {
+            resource.check_loaded()?;
+
+            Ok(resource.integrity()?)
+        })?
+    }
+
+    /// Get another handle of the same resources.
+    #[cfg(any(feature = "tera", feature = "handlebars"))]
+    #[inline]
+    pub(crate) fn share(&self) -> StaticContextManager<N> {
+        StaticContextManager::new(self.resources.share())
+    }
+
+    /// Whether the name is registered.
+    #[inline]
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.resources.contains(name)
+    }
+
+    /// Get the fingerprint of a resource like `fingerprint`, without blocking the executor.
+    #[inline]
+    pub(crate) async fn fingerprint_async(&self, name: &str) -> Result<String, Error> {
+        self.resources
+            .with_resource_async(name, |resource| {
+                resource.check_loaded().map(|_| fingerprint(&resource.etag))
+            })
+            .await?
+    }
+
+    /// Get the fingerprint of a resource, which is derived from its content.
+    #[inline]
+    pub(crate) fn fingerprint<S: AsRef<str>>(&self, name: S) -> Result<String, Error> {
+        self.resources.with_resource(name.as_ref(), |resource| {
+            resource.check_loaded().map(|_| fingerprint(&resource.etag))
+        })?
+    }
+
+    /// Get the fingerprinted name of a resource, such as **js/app.{fingerprint}.js** for **js/app.js**. The fingerprint is derived from the content of the resource, so the name changes whenever the content changes and can be cached forever. `StaticServer` serves fingerprinted names with `Cache-Control: public, max-age=31536000, immutable`.
+    #[inline]
+    pub fn fingerprinted_name<S: AsRef<str>>(&self, name: S) -> Result<String, Error> {
+        let name = name.as_ref();
+
+        self.fingerprint(name).map(|fingerprint| fingerprinted_name(name, &fingerprint))
+    }
+
+    /// Resources are read from their files in the debug profile, so there is no override directory to read again. Returns `Ok(0)`.
+    #[inline]
+    pub fn rescan_overrides(&self) -> Result<usize, io::Error> {
+        Ok(0)
+    }
+
     /// Build a `StaticResponse`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name is not registered or the file cannot be read. Use `try_build` to handle the error instead.
     #[inline]
     pub fn build<S: AsRef<str>>(
         &self,
         etag_if_none_match: &EtagIfNoneMatch<'_>,
         name: S,
     ) -> StaticResponse {
-        self.resources
-            .lock()
-            .unwrap_or_else(PoisonError::into_inner)
-            .get_resource(name.as_ref())
-            .map(|resource| {
-                if etag_if_none_match.weak_eq(resource.2) {
-                    StaticResponse::not_modified()
-                } else {
-                    StaticResponse::build(&resource.0, resource.1.clone(), resource.2)
-                }
-            })
-            .unwrap()
+        self.try_build(etag_if_none_match, name).unwrap()
+    }
+}
+
+impl<N> ResourceStore for StaticContextManager<N> {
+    #[inline]
+    fn get_resource(&self, name: &str) -> Result<ResourceView, Error> {
+        self.resources.get_resource(name)
+    }
+
+    #[inline]
+    fn get_resource_encoded(
+        &self,
+        name: &str,
+        encoding: Encoding,
+    ) -> Result<Option<ResourceView>, Error> {
+        self.resources.get_resource_encoded(name, encoding)
+    }
+
+    #[inline]
+    fn resource_names(&self) -> Vec<String> {
+        self.resources.resource_names()
     }
 }
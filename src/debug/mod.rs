@@ -1,5 +1,10 @@
+mod error_page;
 mod file_resources;
+#[cfg(feature = "live-reload")]
+mod live_reload;
 mod static_response;
+#[cfg(feature = "watch")]
+mod watcher;
 
 mod manager;
 
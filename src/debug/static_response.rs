@@ -1,66 +1,405 @@
-use std::sync::Arc;
+use std::{
+    io::{self, Cursor, ErrorKind, SeekFrom},
+    ops::Range,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+    time::SystemTime,
+};
 
-use mime::Mime;
 use rc_u8_reader::ArcU8Reader;
 
+use super::{
+    error_page::render_error_page,
+    file_resources::{EncodedResources, Resource},
+};
 use crate::{
+    conditional::{evaluate_preconditions, set_validators, Precondition},
+    encoding::{accepted_encodings, Encoding},
+    range::apply_range,
     rocket::{
         http::Status,
         request::Request,
         response::{self, Responder, Response},
+        tokio::{
+            fs::File,
+            io::{AsyncRead, AsyncReadExt, AsyncSeek, ReadBuf, Take},
+            task,
+        },
     },
-    EntityTag,
+    CachePolicy, EntityTag, EtagIfNoneMatch,
 };
+#[cfg(feature = "integrity")]
+use crate::{DigestHeaders, Integrity};
+
+/// A part of shared data, used as the body of a partial response.
+#[derive(Debug)]
+struct ArcSlice {
+    data:  Arc<Vec<u8>>,
+    range: Range<usize>,
+}
+
+impl AsRef<[u8]> for ArcSlice {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.data[self.range.clone()]
+    }
+}
 
+/// A part of a file, streamed as the body of a response. Positions are relative to the start of the part.
 #[derive(Debug)]
-struct StaticResponseInner {
-    mime: String,
-    data: Arc<Vec<u8>>,
-    etag: String,
+struct FileSlice {
+    file:    Take<File>,
+    range:   Range<u64>,
+    /// The position to seek to before the next read.
+    pending: Option<u64>,
+    seeking: bool,
+}
+
+impl FileSlice {
+    #[inline]
+    fn new(file: File, range: Range<u64>) -> FileSlice {
+        FileSlice {
+            file: file.take(range.end - range.start),
+            pending: Some(0),
+            seeking: false,
+            range,
+        }
+    }
+
+    #[inline]
+    fn length(&self) -> u64 {
+        self.range.end - self.range.start
+    }
+
+    /// Drive the pending seek, if any.
+    fn poll_seek(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if let Some(position) = self.pending.take() {
+            Pin::new(self.file.get_mut())
+                .start_seek(SeekFrom::Start(self.range.start + position))?;
+
+            self.file.set_limit(self.length() - position);
+
+            self.seeking = true;
+        }
+
+        if self.seeking {
+            ready!(Pin::new(self.file.get_mut()).poll_complete(cx))?;
+
+            self.seeking = false;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncRead for FileSlice {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        ready!(this.poll_seek(cx))?;
+
+        Pin::new(&mut this.file).poll_read(cx, buf)
+    }
+}
+
+impl AsyncSeek for FileSlice {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+
+        let length = this.length();
+        let current = this.pending.unwrap_or(length - this.file.limit());
+
+        let position = match position {
+            SeekFrom::Start(offset) => i128::from(offset),
+            SeekFrom::End(offset) => i128::from(length) + i128::from(offset),
+            SeekFrom::Current(offset) => i128::from(current) + i128::from(offset),
+        };
+
+        if position < 0 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "invalid seek to a negative position",
+            ));
+        }
+
+        this.pending = Some((position as u64).min(length));
+
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+
+        ready!(this.poll_seek(cx))?;
+
+        Poll::Ready(Ok(this.length() - this.file.limit()))
+    }
+}
+
+/// A file which is streamed instead of being kept in memory. It is opened before the response is sent.
+#[derive(Debug)]
+struct StreamedFile {
+    path:   PathBuf,
+    length: u64,
+    file:   Option<File>,
 }
 
 #[derive(Debug)]
 /// To respond a static resource.
 pub struct StaticResponse {
-    inner: Option<StaticResponseInner>,
+    mime:           String,
+    data:           Arc<Vec<u8>>,
+    streamed:       Option<StreamedFile>,
+    /// The path of the file and the error if it cannot be loaded, in which case an error page is served.
+    load_error:     Option<(PathBuf, Arc<io::Error>)>,
+    etag:           EntityTag<'static>,
+    encoded:        Arc<EncodedResources>,
+    last_modified:  Option<SystemTime>,
+    if_none_match:  Option<EntityTag<'static>>,
+    cache_policy:   CachePolicy,
+    #[cfg(feature = "integrity")]
+    integrity:      Option<Integrity>,
+    #[cfg(feature = "integrity")]
+    digest_headers: DigestHeaders,
 }
 
 impl StaticResponse {
     #[inline]
     pub(crate) fn build(
-        mime: &Mime,
-        data: Arc<Vec<u8>>,
-        etag: &EntityTag<'static>,
+        resource: &Resource,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
     ) -> StaticResponse {
+        #[cfg(feature = "integrity")]
+        let integrity = resource.integrity.clone();
+        #[cfg(feature = "integrity")]
+        let digest_headers = DigestHeaders::new();
+
         StaticResponse {
-            inner: Some(StaticResponseInner {
-                mime: mime.to_string(),
-                data,
-                etag: etag.to_string(),
+            mime: resource.mime.to_string(),
+            data: resource.data.clone(),
+            streamed: resource.streamed_length().map(|(path, length)| StreamedFile {
+                path: path.to_path_buf(),
+                length,
+                file: None,
             }),
+            load_error: resource
+                .load_error()
+                .map(|(path, error)| (path.to_path_buf(), error.clone())),
+            etag: resource.etag.clone(),
+            encoded: resource.encoded.clone(),
+            last_modified: resource.mtime,
+            if_none_match: etag_if_none_match.etag.clone().map(EntityTag::into_owned),
+            cache_policy: CachePolicy::new(),
+            #[cfg(feature = "integrity")]
+            integrity,
+            #[cfg(feature = "integrity")]
+            digest_headers,
         }
     }
 
+    /// Set the `Cache-Control` policy of the resource.
     #[inline]
-    pub(crate) const fn not_modified() -> StaticResponse {
-        StaticResponse {
-            inner: None
+    pub(crate) fn with_cache_policy(mut self, cache_policy: CachePolicy) -> StaticResponse {
+        self.cache_policy = cache_policy;
+
+        self
+    }
+
+    /// Set which digest headers are sent.
+    #[cfg(feature = "integrity")]
+    #[inline]
+    pub(crate) fn with_digest_headers(mut self, digest_headers: DigestHeaders) -> StaticResponse {
+        self.digest_headers = digest_headers;
+
+        self
+    }
+
+    /// Open the file of a streamed resource, or load the encoded variants of the resource. If the file cannot be opened, an error page is served instead.
+    #[inline]
+    pub(crate) fn open(self) -> StaticResponse {
+        self.encoded.load_all();
+
+        let result = match self.streamed.as_ref() {
+            Some(streamed) => std::fs::File::open(&streamed.path).map(File::from_std),
+            None => return self,
+        };
+
+        self.with_file(result)
+    }
+
+    /// Open the file of a streamed resource, or load the encoded variants of the resource, without blocking the executor. If the file cannot be opened, an error page is served instead.
+    #[inline]
+    pub(crate) async fn open_async(self) -> StaticResponse {
+        if self.encoded.needs_loading() {
+            let encoded = self.encoded.clone();
+
+            // if the task fails, the resource is served without encoded variants
+            let _ = task::spawn_blocking(move || encoded.load_all()).await;
         }
+
+        let result = match self.streamed.as_ref() {
+            Some(streamed) => File::open(&streamed.path).await,
+            None => return self,
+        };
+
+        self.with_file(result)
+    }
+
+    #[inline]
+    fn with_file(mut self, result: Result<File, io::Error>) -> StaticResponse {
+        if let Some(mut streamed) = self.streamed.take() {
+            match result {
+                Ok(file) => {
+                    streamed.file = Some(file);
+
+                    self.streamed = Some(streamed);
+                },
+                Err(error) => {
+                    rocket::warn!(
+                        "Failed to open {:?}, so an error page is served: {}",
+                        streamed.path,
+                        error
+                    );
+
+                    self.load_error = Some((streamed.path, Arc::new(error)));
+                },
+            }
+        }
+
+        self
+    }
+}
+
+/// Set the `Cache-Control` header, which is needed by both `200 OK` and `304 Not Modified` responses.
+#[inline]
+fn set_cache_control(response: &mut response::Builder<'_>, cache_policy: &CachePolicy) {
+    if !cache_policy.is_empty() {
+        response.raw_header("Cache-Control", cache_policy.to_string());
     }
 }
 
 impl<'r, 'o: 'r> Responder<'r, 'o> for StaticResponse {
     #[inline]
-    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'o> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'o> {
         let mut response = Response::build();
 
-        if let Some(inner) = self.inner {
-            response.raw_header("Etag", inner.etag);
-            response.raw_header("Content-Type", inner.mime);
+        if let Some((path, error)) = self.load_error {
+            let html = render_error_page(&path, &error);
 
-            response.sized_body(inner.data.len(), ArcU8Reader::new(inner.data));
+            response
+                .status(if error.kind() == ErrorKind::NotFound {
+                    Status::NotFound
+                } else {
+                    Status::InternalServerError
+                })
+                .raw_header("Content-Type", "text/html; charset=utf-8")
+                .raw_header("Cache-Control", "no-store")
+                .sized_body(html.len(), Cursor::new(html));
+
+            return response.ok();
+        }
+
+        let has_variants = self.encoded.has_variants();
+
+        let variant: Option<(Encoding, _)> = if has_variants {
+            accepted_encodings(request.headers().get_one("Accept-Encoding"))
+                .into_iter()
+                .find_map(|encoding| self.encoded.get(encoding).map(|e| (encoding, e)))
         } else {
-            response.status(Status::NotModified);
+            None
+        };
+
+        #[cfg(feature = "integrity")]
+        let integrity = match variant.as_ref() {
+            Some((_, variant)) => Some(variant.integrity.clone()),
+            None => self.integrity,
+        };
+
+        let (encoding, data, etag) = match variant {
+            Some((encoding, variant)) => (Some(encoding), variant.data, variant.etag),
+            None => (None, self.data, self.etag),
+        };
+
+        if has_variants {
+            response.raw_header("Vary", "Accept-Encoding");
+        }
+
+        match evaluate_preconditions(
+            request,
+            self.if_none_match.as_ref(),
+            &etag,
+            self.last_modified,
+        ) {
+            Precondition::Failed => {
+                response.status(Status::PreconditionFailed);
+            },
+            Precondition::NotModified => {
+                response.status(Status::NotModified);
+
+                set_validators(&mut response, &etag, self.last_modified);
+                set_cache_control(&mut response, &self.cache_policy);
+            },
+            Precondition::Proceed => {
+                set_validators(&mut response, &etag, self.last_modified);
+                set_cache_control(&mut response, &self.cache_policy);
+
+                if let Some(encoding) = encoding {
+                    response.raw_header("Content-Encoding", encoding.as_str());
+                }
+
+                if let Some(streamed) = self.streamed {
+                    let file = streamed.file.ok_or(Status::InternalServerError)?;
+
+                    if let Some(range) = apply_range(
+                        &mut response,
+                        request,
+                        &self.mime,
+                        &etag,
+                        self.last_modified,
+                        streamed.length as usize,
+                        None,
+                    ) {
+                        let range = range.start as u64..range.end as u64;
+
+                        response.sized_body(
+                            (range.end - range.start) as usize,
+                            FileSlice::new(file, range),
+                        );
+                    }
+                } else if let Some(range) = apply_range(
+                    &mut response,
+                    request,
+                    &self.mime,
+                    &etag,
+                    self.last_modified,
+                    data.len(),
+                    Some(&data),
+                ) {
+                    #[cfg(feature = "integrity")]
+                    self.digest_headers.apply(
+                        &mut response,
+                        integrity.as_ref(),
+                        range.len() == data.len(),
+                    );
+
+                    if range.len() == data.len() {
+                        response.sized_body(data.len(), ArcU8Reader::new(data));
+                    } else {
+                        let data = ArcSlice {
+                            data,
+                            range,
+                        };
+
+                        response.sized_body(data.range.len(), ArcU8Reader::new(Arc::new(data)));
+                    }
+                }
+            },
         }
 
         response.ok()
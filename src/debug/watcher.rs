@@ -0,0 +1,135 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Debug, Formatter},
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// The changes noticed by a `FileWatcher` since the last time they were taken.
+#[derive(Debug)]
+pub(crate) enum Changes {
+    /// The paths of the changed files and directories.
+    Paths(HashSet<PathBuf>),
+    /// Some events may have been missed, so everything needs to be checked.
+    All,
+}
+
+/// The changes noticed by a `FileWatcher`. It can be shared, so that whether anything has changed can be checked without the lock of the resources.
+#[derive(Debug, Default)]
+pub(crate) struct WatcherState {
+    changed: AtomicBool,
+    all:     AtomicBool,
+    paths:   Mutex<HashSet<PathBuf>>,
+}
+
+impl WatcherState {
+    /// Whether any change has been noticed since the last time they were taken.
+    #[inline]
+    pub(crate) fn has_changes(&self) -> bool {
+        self.changed.load(Ordering::Acquire)
+    }
+}
+
+/// Watches the files of resources through the file system notification of the OS (e.g. inotify), so that files only need to be checked after they change.
+pub(crate) struct FileWatcher {
+    watcher: RecommendedWatcher,
+    watched: HashSet<PathBuf>,
+    state:   Arc<WatcherState>,
+}
+
+impl Debug for FileWatcher {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        f.debug_struct("FileWatcher").field("watched", &self.watched).finish()
+    }
+}
+
+impl FileWatcher {
+    pub(crate) fn new() -> Result<FileWatcher, io::Error> {
+        let state = Arc::new(WatcherState::default());
+
+        let handler_state = state.clone();
+
+        let watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+            match result {
+                Ok(event) => {
+                    if event.need_rescan() {
+                        handler_state.all.store(true, Ordering::Release);
+                    } else if matches!(event.kind, EventKind::Access(_)) {
+                        return;
+                    } else {
+                        handler_state
+                            .paths
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .extend(event.paths);
+                    }
+                },
+                Err(_) => handler_state.all.store(true, Ordering::Release),
+            }
+
+            handler_state.changed.store(true, Ordering::Release);
+        })
+        .map_err(to_io_error)?;
+
+        Ok(FileWatcher {
+            watcher,
+            watched: HashSet::new(),
+            state,
+        })
+    }
+
+    /// Watch a directory. Watching the same directory again does nothing.
+    pub(crate) fn watch(&mut self, path: &Path, recursive: bool) -> Result<(), io::Error> {
+        if self.watched.contains(path) {
+            return Ok(());
+        }
+
+        let mode = if recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+
+        self.watcher.watch(path, mode).map_err(to_io_error)?;
+
+        self.watched.insert(path.to_path_buf());
+
+        Ok(())
+    }
+
+    /// Get the shared state of the changes.
+    #[inline]
+    pub(crate) fn state(&self) -> Arc<WatcherState> {
+        self.state.clone()
+    }
+
+    /// Take the changes noticed so far. Returns `None` if nothing has changed, which only costs an atomic operation.
+    #[inline]
+    pub(crate) fn take_changes(&self) -> Option<Changes> {
+        if !self.state.changed.swap(false, Ordering::AcqRel) {
+            return None;
+        }
+
+        let paths =
+            std::mem::take(&mut *self.state.paths.lock().unwrap_or_else(PoisonError::into_inner));
+
+        if self.state.all.swap(false, Ordering::AcqRel) {
+            Some(Changes::All)
+        } else {
+            Some(Changes::Paths(paths))
+        }
+    }
+}
+
+#[inline]
+fn to_io_error(error: notify::Error) -> io::Error {
+    match error {
+        notify::Error {
+            kind: notify::ErrorKind::Io(error), ..
+        } => error,
+        error => io::Error::new(io::ErrorKind::Other, error),
+    }
+}
@@ -0,0 +1,115 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::{rocket::response, DigestAlgorithm, Integrity};
+
+/// Which digest headers of RFC 9530 are sent with resources. It can be set by `resources.set_digest_headers(...)` in the closure passed to `StaticResponse::fairing`.
+///
+/// `Repr-Digest` carries the digests of the selected representation (e.g. the gzip variant), so it is sent with `200 OK` and `206 Partial Content` responses. `Content-Digest` carries the digests of the body, so it is only sent when the whole representation is sent. Only `sha-256` and `sha-512` are registered for the headers, and `sha-256` is used if no algorithm is chosen.
+///
+/// ```rust,ignore
+/// StaticResponse::fairing(|resources| {
+///     resources.set_digest_headers(DigestHeaders::new().repr_digest().content_digest().sha512());
+/// })
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DigestHeaders {
+    repr_digest:    bool,
+    content_digest: bool,
+    sha256:         bool,
+    sha512:         bool,
+}
+
+impl DigestHeaders {
+    /// Send no digest headers.
+    #[inline]
+    pub const fn new() -> DigestHeaders {
+        DigestHeaders {
+            repr_digest:    false,
+            content_digest: false,
+            sha256:         false,
+            sha512:         false,
+        }
+    }
+
+    /// Send the `Repr-Digest` header.
+    #[inline]
+    pub const fn repr_digest(mut self) -> DigestHeaders {
+        self.repr_digest = true;
+
+        self
+    }
+
+    /// Send the `Content-Digest` header.
+    #[inline]
+    pub const fn content_digest(mut self) -> DigestHeaders {
+        self.content_digest = true;
+
+        self
+    }
+
+    /// Include the SHA-256 digest.
+    #[inline]
+    pub const fn sha256(mut self) -> DigestHeaders {
+        self.sha256 = true;
+
+        self
+    }
+
+    /// Include the SHA-512 digest.
+    #[inline]
+    pub const fn sha512(mut self) -> DigestHeaders {
+        self.sha512 = true;
+
+        self
+    }
+
+    /// Whether no digest headers are sent.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        !self.repr_digest && !self.content_digest
+    }
+
+    /// Format the value of the headers, such as `sha-256=:{base64 digest}:`.
+    fn value(&self, integrity: &Integrity) -> String {
+        let mut algorithms = Vec::with_capacity(2);
+
+        if self.sha256 || !self.sha512 {
+            algorithms.push(("sha-256", DigestAlgorithm::Sha256));
+        }
+
+        if self.sha512 {
+            algorithms.push(("sha-512", DigestAlgorithm::Sha512));
+        }
+
+        algorithms
+            .into_iter()
+            .map(|(name, algorithm)| {
+                format!("{}=:{}:", name, STANDARD.encode(integrity.digest(algorithm)))
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Set the headers for a representation whose digests are `integrity`. `complete` is whether the body is the whole representation.
+    pub(crate) fn apply(
+        &self,
+        response: &mut response::Builder<'_>,
+        integrity: Option<&Integrity>,
+        complete: bool,
+    ) {
+        let integrity = match integrity {
+            Some(integrity) if !self.is_empty() => integrity,
+            _ => return,
+        };
+
+        let value = self.value(integrity);
+
+        if self.content_digest && complete {
+            response.raw_header("Content-Digest", value.clone());
+        }
+
+        if self.repr_digest {
+            response.raw_header("Repr-Digest", value);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    cmp::Reverse,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+/// Content codings which a static resource can be precompressed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Brotli,
+    Zstd,
+    Gzip,
+}
+
+impl Encoding {
+    /// All the supported encodings, in the order the server prefers them.
+    pub const ALL: [Encoding; 3] = [Encoding::Brotli, Encoding::Zstd, Encoding::Gzip];
+
+    /// The content-coding token used in the `Accept-Encoding` and `Content-Encoding` headers.
+    #[inline]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Zstd => "zstd",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    /// The file extension of a sidecar file which contains data precompressed with this encoding, such as `app.js.br`.
+    #[inline]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Zstd => "zst",
+            Encoding::Gzip => "gz",
+        }
+    }
+
+    /// Whether this crate is able to compress data with this encoding by itself. It depends on the `brotli`, `zstd` and `gzip` features.
+    #[inline]
+    pub const fn is_enabled(self) -> bool {
+        match self {
+            Encoding::Brotli => cfg!(feature = "brotli"),
+            Encoding::Zstd => cfg!(feature = "zstd"),
+            Encoding::Gzip => cfg!(feature = "gzip"),
+        }
+    }
+
+    /// The path of the sidecar file of `path` which contains data precompressed with this encoding, such as `app.js.br`.
+    #[inline]
+    pub(crate) fn sidecar_path(self, path: &Path) -> PathBuf {
+        let mut sidecar_path = OsString::from(path);
+
+        sidecar_path.push(".");
+        sidecar_path.push(self.extension());
+
+        PathBuf::from(sidecar_path)
+    }
+
+    /// Split a sidecar file path like `app.js.br` into the path of the original file and the encoding.
+    #[inline]
+    pub(crate) fn split_sidecar_path(path: &str) -> Option<(&str, Encoding)> {
+        let (original_path, extension) = path.rsplit_once('.')?;
+
+        let encoding = Encoding::ALL.iter().copied().find(|e| e.extension() == extension)?;
+
+        Some((original_path, encoding))
+    }
+
+    #[inline]
+    pub(crate) fn from_token(token: &str) -> Option<Encoding> {
+        if token.eq_ignore_ascii_case("br") {
+            Some(Encoding::Brotli)
+        } else if token.eq_ignore_ascii_case("zstd") {
+            Some(Encoding::Zstd)
+        } else if token.eq_ignore_ascii_case("gzip") || token.eq_ignore_ascii_case("x-gzip") {
+            Some(Encoding::Gzip)
+        } else {
+            None
+        }
+    }
+
+    /// Compress `data`. Returns `None` if the encoding is not enabled or the compressed data is not smaller than the original one.
+    #[inline]
+    pub(crate) fn compress(self, data: &[u8]) -> Option<Vec<u8>> {
+        rocket_include_static_resources_compression::compress(self.as_str(), data)
+    }
+}
+
+/// Whether it is worth compressing data of this MIME type.
+pub(crate) use rocket_include_static_resources_compression::is_compressible;
+
+/// Parse an `Accept-Encoding` header and return the acceptable encodings, the most preferred one first.
+pub(crate) fn accepted_encodings(accept_encoding: Option<&str>) -> Vec<Encoding> {
+    let accept_encoding = match accept_encoding {
+        Some(accept_encoding) => accept_encoding,
+        None => return Vec::new(),
+    };
+
+    // (encoding, q-value in thousandths); `None` is for `*`
+    let mut codings: Vec<(Option<Encoding>, u16)> = Vec::new();
+
+    for item in accept_encoding.split(',') {
+        let mut parts = item.split(';');
+
+        let token = parts.next().unwrap_or("").trim();
+
+        let q = parts
+            .filter_map(|param| {
+                let (key, value) = param.split_once('=')?;
+
+                if key.trim().eq_ignore_ascii_case("q") {
+                    Some(parse_q_value(value.trim()))
+                } else {
+                    None
+                }
+            })
+            .next_back()
+            .unwrap_or(1000);
+
+        if token == "*" {
+            codings.push((None, q));
+        } else if let Some(encoding) = Encoding::from_token(token) {
+            codings.push((Some(encoding), q));
+        }
+    }
+
+    let wildcard_q = codings.iter().find(|(encoding, _)| encoding.is_none()).map(|(_, q)| *q);
+
+    let mut encodings: Vec<(Encoding, u16)> = Encoding::ALL
+        .iter()
+        .filter_map(|&encoding| {
+            let q = codings
+                .iter()
+                .find(|(e, _)| *e == Some(encoding))
+                .map(|(_, q)| *q)
+                .or(wildcard_q)?;
+
+            if q > 0 {
+                Some((encoding, q))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    // stable, so the server preference is kept among the same q-values
+    encodings.sort_by_key(|(_, q)| Reverse(*q));
+
+    encodings.into_iter().map(|(encoding, _)| encoding).collect()
+}
+
+#[inline]
+fn parse_q_value(value: &str) -> u16 {
+    match value.parse::<f32>() {
+        Ok(q) if (0.0..=1.0).contains(&q) => (q * 1000.0).round() as u16,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn server_preference() {
+        assert_eq!(Vec::<Encoding>::new(), accepted_encodings(None));
+        assert_eq!(vec![Encoding::Brotli, Encoding::Gzip], accepted_encodings(Some("gzip, br")));
+        assert_eq!(vec![Encoding::Gzip], accepted_encodings(Some("x-gzip, identity, deflate")));
+        assert_eq!(vec![Encoding::Gzip], accepted_encodings(Some("GZIP")));
+    }
+
+    #[test]
+    fn q_values() {
+        assert_eq!(
+            vec![Encoding::Brotli, Encoding::Gzip],
+            accepted_encodings(Some("gzip;q=0.5, br; q=0.8"))
+        );
+        assert_eq!(
+            vec![Encoding::Gzip, Encoding::Brotli],
+            accepted_encodings(Some("gzip;q=1, br;q=0.5, zstd;q=0"))
+        );
+        assert_eq!(vec![Encoding::Gzip], accepted_encodings(Some("gzip;q=0.001, br;q=0")));
+        // invalid q-values are treated as `q=0`
+        assert_eq!(vec![Encoding::Brotli], accepted_encodings(Some("gzip;q=2, br")));
+    }
+
+    #[test]
+    fn wildcard() {
+        assert_eq!(Encoding::ALL.to_vec(), accepted_encodings(Some("*")));
+        assert_eq!(Vec::<Encoding>::new(), accepted_encodings(Some("*;q=0")));
+        // explicit codings take precedence over `*`
+        assert_eq!(vec![Encoding::Zstd, Encoding::Gzip], accepted_encodings(Some("br;q=0, *")));
+        assert_eq!(vec![Encoding::Gzip], accepted_encodings(Some("gzip, *;q=0")));
+        assert_eq!(
+            vec![Encoding::Gzip, Encoding::Brotli, Encoding::Zstd],
+            accepted_encodings(Some("gzip, *;q=0.5"))
+        );
+    }
+
+    #[test]
+    fn sidecar_paths() {
+        assert_eq!(
+            Some(("js/app.js", Encoding::Brotli)),
+            Encoding::split_sidecar_path("js/app.js.br")
+        );
+        assert_eq!(
+            Some(("app.min.js", Encoding::Zstd)),
+            Encoding::split_sidecar_path("app.min.js.zst")
+        );
+        assert_eq!(None, Encoding::split_sidecar_path("js/app.js"));
+    }
+}
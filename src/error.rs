@@ -0,0 +1,80 @@
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    io,
+};
+
+use crate::rocket::{
+    http::Status,
+    request::Request,
+    response::{self, Responder},
+};
+
+/// Errors occurring when building responses of static resources.
+#[derive(Debug)]
+pub enum Error {
+    /// The name is not registered (or the file has been removed from its directory).
+    NotFound(String),
+    /// The resource cannot be read.
+    IOError(io::Error),
+}
+
+impl Error {
+    /// The status of the response for this error.
+    #[inline]
+    pub fn status(&self) -> Status {
+        match self {
+            Error::NotFound(_) => Status::NotFound,
+            Error::IOError(_) => Status::InternalServerError,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    #[inline]
+    fn from(error: io::Error) -> Self {
+        Error::IOError(error)
+    }
+}
+
+impl From<Error> for io::Error {
+    #[inline]
+    fn from(error: Error) -> Self {
+        match error {
+            Error::NotFound(_) => io::Error::new(io::ErrorKind::NotFound, error.to_string()),
+            Error::IOError(error) => error,
+        }
+    }
+}
+
+impl Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Error::NotFound(name) => f.write_fmt(format_args!("The name `{}` is not found.", name)),
+            Error::IOError(error) => Display::fmt(error, f),
+        }
+    }
+}
+
+impl error::Error for Error {
+    #[inline]
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::NotFound(_) => None,
+            Error::IOError(error) => Some(error),
+        }
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for Error {
+    /// `404 Not Found` for an unknown name, and `500 Internal Server Error` for an I/O failure.
+    #[inline]
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'o> {
+        if let Error::IOError(error) = &self {
+            rocket::error!("Failed to read a static resource: {}", error);
+        }
+
+        Err(self.status())
+    }
+}
@@ -0,0 +1,122 @@
+use crate::EntityTag;
+
+/// The length of fingerprints, which are URL-safe base64 encoded 64-bit hashes.
+const FINGERPRINT_LENGTH: usize = 11;
+
+/// The `Cache-Control` header of responses requested by the up-to-date fingerprinted names.
+pub(crate) const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Derive a fingerprint from the ETag of a resource, which is computed from its content.
+#[inline]
+pub(crate) fn fingerprint(etag: &EntityTag<'_>) -> String {
+    etag.get_tag()
+        .chars()
+        .map(|c| match c {
+            '+' => '-',
+            '/' => '_',
+            _ => c,
+        })
+        .collect()
+}
+
+#[inline]
+fn is_fingerprint(s: &str) -> bool {
+    s.len() == FINGERPRINT_LENGTH
+        && s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+/// Insert a fingerprint before the extension of the file name, e.g. **js/app.js** becomes **js/app.{fingerprint}.js**. Names without extensions are suffixed with the fingerprint.
+pub(crate) fn fingerprinted_name(name: &str, fingerprint: &str) -> String {
+    let file_name_index = name.rfind('/').map(|i| i + 1).unwrap_or(0);
+
+    match name[file_name_index..].rfind('.') {
+        Some(i) if i > 0 => {
+            let (stem, extension) = name.split_at(file_name_index + i);
+
+            format!("{}.{}{}", stem, fingerprint, extension)
+        },
+        _ => format!("{}.{}", name, fingerprint),
+    }
+}
+
+/// Split a fingerprinted name into the original name and the fingerprint. It is the reverse of `fingerprinted_name`.
+pub(crate) fn split_fingerprint(name: &str) -> Option<(String, &str)> {
+    let file_name_index = name.rfind('/').map(|i| i + 1).unwrap_or(0);
+
+    let (directory, file_name) = name.split_at(file_name_index);
+
+    let mut parts = file_name.rsplitn(3, '.');
+
+    let last = parts.next()?;
+    let second_last = parts.next()?;
+
+    let candidates = [
+        // {stem}.{fingerprint}.{extension}
+        parts
+            .next()
+            .filter(|_| is_fingerprint(second_last))
+            .map(|stem| (format!("{}{}.{}", directory, stem, last), second_last)),
+        // {name}.{fingerprint}
+        Some(last)
+            .filter(|last| is_fingerprint(last))
+            .map(|last| (name[..name.len() - last.len() - 1].to_string(), last)),
+    ];
+
+    candidates
+        .into_iter()
+        .flatten()
+        .find(|(original_name, fingerprint)| fingerprinted_name(original_name, fingerprint) == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FINGERPRINT: &str = "AbC-dEf_012";
+
+    #[test]
+    fn fingerprint_of_etag() {
+        let fingerprint = fingerprint(&EntityTag::from_data(b"body { color: red; }"));
+
+        assert!(is_fingerprint(&fingerprint), "{:?}", fingerprint);
+    }
+
+    #[test]
+    fn fingerprinted_names() {
+        assert_eq!("js/app.AbC-dEf_012.js", fingerprinted_name("js/app.js", FINGERPRINT));
+        assert_eq!(
+            "js/jquery.min.AbC-dEf_012.js",
+            fingerprinted_name("js/jquery.min.js", FINGERPRINT)
+        );
+        assert_eq!("LICENSE.AbC-dEf_012", fingerprinted_name("LICENSE", FINGERPRINT));
+        assert_eq!("v1.2/app.AbC-dEf_012", fingerprinted_name("v1.2/app", FINGERPRINT));
+        assert_eq!(".env.AbC-dEf_012", fingerprinted_name(".env", FINGERPRINT));
+    }
+
+    #[test]
+    fn split_fingerprints() {
+        for name in
+            ["js/app.js", "js/jquery.min.js", "archive.tar.gz", "LICENSE", "v1.2/app", ".env"]
+        {
+            assert_eq!(
+                Some((name.to_string(), FINGERPRINT)),
+                split_fingerprint(&fingerprinted_name(name, FINGERPRINT)),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn split_names_without_fingerprints() {
+        assert_eq!(None, split_fingerprint("js/app.js"));
+        assert_eq!(None, split_fingerprint("js/jquery.min.js"));
+        assert_eq!(None, split_fingerprint("LICENSE"));
+        assert_eq!(None, split_fingerprint("v1.2.3/app.js"));
+        // too short or containing characters out of URL-safe base64
+        assert_eq!(None, split_fingerprint("js/app.AbC-dEf_01.js"));
+        assert_eq!(None, split_fingerprint("js/app.AbC+dEf/012.js"));
+        // the fingerprint must be in the file name
+        assert_eq!(None, split_fingerprint("AbC-dEf_012/app"));
+    }
+}
@@ -1,6 +1,151 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use glob::{MatchOptions, Pattern};
+
 use crate::EntityTag;
 
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive:              true,
+    require_literal_separator:   true,
+    require_literal_leading_dot: false,
+};
+
 #[inline]
 pub(crate) fn compute_data_etag<B: AsRef<[u8]> + ?Sized>(data: &B) -> EntityTag<'static> {
     EntityTag::from_data(data)
 }
+
+/// Compute an ETag from the length and the modification time of a file, for files which are not read into memory.
+#[cfg(not(static_resources_embed))]
+#[inline]
+pub(crate) fn compute_file_etag(length: u64, mtime: Option<SystemTime>) -> EntityTag<'static> {
+    let mut key = length.to_le_bytes().to_vec();
+
+    if let Some(Ok(time)) = mtime.map(|mtime| mtime.duration_since(UNIX_EPOCH)) {
+        key.extend_from_slice(&time.as_nanos().to_le_bytes());
+    }
+
+    EntityTag::from_data(&key)
+}
+
+/// The time this crate was built, which is the `SOURCE_DATE_EPOCH` environment variable at compile time if it is set, so that builds stay reproducible.
+#[inline]
+pub(crate) fn build_time() -> SystemTime {
+    env!("ROCKET_INCLUDE_STATIC_RESOURCES_BUILD_TIME")
+        .parse::<u64>()
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+        .unwrap_or(UNIX_EPOCH)
+}
+
+/// The `Last-Modified` time of an embedded file, which is its modification time at compile time if it is known, or `build_time`.
+#[inline]
+pub(crate) fn embedded_last_modified(modified: Option<u64>, build_time: SystemTime) -> SystemTime {
+    modified.map(|secs| UNIX_EPOCH + Duration::from_secs(secs)).unwrap_or(build_time)
+}
+
+/// Compile the `include` or `exclude` glob patterns of a directory.
+#[inline]
+pub(crate) fn compile_patterns(patterns: &[&str]) -> Result<Vec<Pattern>, io::Error> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Pattern::new(pattern).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+        })
+        .collect()
+}
+
+/// Whether a relative path in a directory is matched by any `include` pattern (if there is any) and not matched by any `exclude` pattern.
+#[inline]
+pub(crate) fn is_matched(relative_path: &str, include: &[Pattern], exclude: &[Pattern]) -> bool {
+    (include.is_empty() || include.iter().any(|p| p.matches_with(relative_path, MATCH_OPTIONS)))
+        && !exclude.iter().any(|p| p.matches_with(relative_path, MATCH_OPTIONS))
+}
+
+/// Collect the files under `directory` recursively, with their paths relative to `root` (separated by `/`).
+pub(crate) fn collect_files(root: &Path, directory: &Path, files: &mut Vec<(String, PathBuf)>) {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+
+        if path.is_dir() {
+            collect_files(root, &path, files);
+        } else if path.is_file() {
+            if let Ok(relative_path) = path.strip_prefix(root) {
+                let relative_path = relative_path
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+
+                files.push((relative_path, path));
+            }
+        }
+    }
+}
+
+/// The SHA-256, SHA-384 and SHA-512 digests of an embedded file, computed at compile time by `embed_file!` and `embed_directory!` if the `integrity` feature is enabled, or `None` otherwise.
+#[doc(hidden)]
+pub type EmbeddedDigests = Option<([u8; 32], [u8; 48], [u8; 64])>;
+
+/// The encoded variants of an embedded file as `(content-coding token, data, digests)`, which are its sidecar files or compressed at compile time by `embed_file!` and `embed_directory!`.
+#[doc(hidden)]
+pub type EmbeddedVariants = &'static [(&'static str, &'static [u8], EmbeddedDigests)];
+
+/// A file embedded by `embed_file!` as `(data, digests, encoded variants, modification time in seconds since the Unix epoch)`.
+#[doc(hidden)]
+pub type EmbeddedFile = (&'static [u8], EmbeddedDigests, EmbeddedVariants, Option<u64>);
+
+/// A file embedded by `embed_directory!` as `EmbeddedFile` following its relative path.
+#[doc(hidden)]
+pub type EmbeddedDirectoryFile =
+    (&'static str, &'static [u8], EmbeddedDigests, EmbeddedVariants, Option<u64>);
+
+/// Whether any name in `names` is repeating. It is evaluated at compile time by `static_resources_initializer!`.
+#[doc(hidden)]
+pub const fn has_repeating_names(names: &[&str]) -> bool {
+    let mut i = 0;
+
+    while i < names.len() {
+        let mut j = i + 1;
+
+        while j < names.len() {
+            if str_eq(names[i], names[j]) {
+                return true;
+            }
+
+            j += 1;
+        }
+
+        i += 1;
+    }
+
+    false
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut i = 0;
+
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+
+        i += 1;
+    }
+
+    true
+}
@@ -0,0 +1,100 @@
+use std::{any::type_name, marker::PhantomData, ops::Deref};
+
+use crate::{
+    rocket::{
+        http::Status,
+        request::{FromRequest, Outcome, Request},
+        Ignite, Rocket, Sentinel,
+    },
+    StaticContextManager,
+};
+
+const ALLOW_UNREGISTERED_KEY: &str = "static_resources_allow_unregistered";
+
+/// The resource served by a route generated by `static_response_handler!`. It is implemented for the struct which Rocket generates for the route.
+#[doc(hidden)]
+pub trait RouteResource {
+    /// The name of the resource.
+    fn resource_name() -> String;
+}
+
+/// The request guard of the routes generated by `static_response_handler!`, which dereferences to the `StaticContextManager` of the namespace `N`.
+///
+/// As a `Sentinel`, it makes Rocket refuse to launch if the fairing of static resources is not attached. It also checks whether the resource of the route is registered, which aborts the launch in the release profile, unless `static_resources_allow_unregistered` is `true` in **Rocket.toml** (for resources registered after the launch), and only warns in the debug profile (where the file may be added later).
+#[doc(hidden)]
+pub struct RouteResources<'r, R, N = ()> {
+    manager: &'r StaticContextManager<N>,
+    route:   PhantomData<R>,
+}
+
+impl<R, N> Deref for RouteResources<'_, R, N> {
+    type Target = StaticContextManager<N>;
+
+    #[inline]
+    fn deref(&self) -> &StaticContextManager<N> {
+        self.manager
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, R, N: 'static> FromRequest<'r> for RouteResources<'r, R, N> {
+    type Error = ();
+
+    #[inline]
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request.rocket().state::<StaticContextManager<N>>() {
+            Some(manager) => Outcome::Success(RouteResources {
+                manager,
+                route: PhantomData,
+            }),
+            None => Outcome::Error((Status::InternalServerError, ())),
+        }
+    }
+}
+
+impl<R: RouteResource, N: 'static> Sentinel for RouteResources<'_, R, N> {
+    fn abort(rocket: &Rocket<Ignite>) -> bool {
+        let manager = match rocket.state::<StaticContextManager<N>>() {
+            Some(manager) => manager,
+            None => {
+                rocket::error!(
+                    "Routes of static resources in the namespace `{}` are mounted, but the \
+                     fairing which registers the resources is not attached. Attach \
+                     `static_resources_initializer!` (or the `fairing` of an enum generated by \
+                     `static_resources!`) with the same namespace.",
+                    type_name::<N>()
+                );
+
+                return true;
+            },
+        };
+
+        let name = R::resource_name();
+
+        if manager.contains(&name) {
+            return false;
+        }
+
+        let allow_unregistered =
+            rocket.figment().extract_inner::<bool>(ALLOW_UNREGISTERED_KEY).unwrap_or(false);
+
+        if cfg!(static_resources_embed) && !allow_unregistered {
+            rocket::error!(
+                "The static resource `{}` served by a route is not registered. If it is \
+                 registered after the launch, set `{}` to `true` in Rocket.toml.",
+                name,
+                ALLOW_UNREGISTERED_KEY
+            );
+
+            true
+        } else {
+            rocket::warn!(
+                "The static resource `{}` served by a route is not registered yet, so it responds \
+                 `404 Not Found` until it is added.",
+                name
+            );
+
+            false
+        }
+    }
+}
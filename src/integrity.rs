@@ -0,0 +1,134 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{self, Read, Write},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+#[cfg(static_resources_embed)]
+use crate::EmbeddedDigests;
+
+/// The hash algorithms whose digests are kept in `Integrity`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DigestAlgorithm {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// The name of the algorithm in Subresource Integrity metadata, such as `sha384`.
+    #[inline]
+    pub const fn sri_name(self) -> &'static str {
+        match self {
+            DigestAlgorithm::Sha256 => "sha256",
+            DigestAlgorithm::Sha384 => "sha384",
+            DigestAlgorithm::Sha512 => "sha512",
+        }
+    }
+}
+
+/// The SHA-256, SHA-384 and SHA-512 digests of the data of a resource. They are computed at compile time for embedded resources, and whenever files are loaded in the debug profile.
+///
+/// `Display` formats the Subresource Integrity metadata with SHA-384, such as **sha384-{base64 digest}**, which can be used as the `integrity` attribute of `<script>` and `<link>` elements.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Integrity {
+    sha256: [u8; 32],
+    sha384: [u8; 48],
+    sha512: [u8; 64],
+}
+
+impl Integrity {
+    /// Compute the digests of data.
+    #[inline]
+    pub fn from_data<B: AsRef<[u8]> + ?Sized>(data: &B) -> Integrity {
+        let data = data.as_ref();
+
+        Integrity {
+            sha256: Sha256::digest(data).into(),
+            sha384: Sha384::digest(data).into(),
+            sha512: Sha512::digest(data).into(),
+        }
+    }
+
+    /// Compute the digests of data read from a reader, such as a file which is too large to be read into memory.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Integrity, io::Error> {
+        let mut hashers = Hashers(Sha256::new(), Sha384::new(), Sha512::new());
+
+        io::copy(&mut reader, &mut hashers)?;
+
+        Ok(Integrity {
+            sha256: hashers.0.finalize().into(),
+            sha384: hashers.1.finalize().into(),
+            sha512: hashers.2.finalize().into(),
+        })
+    }
+
+    /// Use digests which have been computed, such as at compile time.
+    #[cfg(static_resources_embed)]
+    #[inline]
+    pub(crate) const fn from_digests(
+        sha256: [u8; 32],
+        sha384: [u8; 48],
+        sha512: [u8; 64],
+    ) -> Integrity {
+        Integrity {
+            sha256,
+            sha384,
+            sha512,
+        }
+    }
+
+    /// The digest of a specific algorithm.
+    #[inline]
+    pub fn digest(&self, algorithm: DigestAlgorithm) -> &[u8] {
+        match algorithm {
+            DigestAlgorithm::Sha256 => &self.sha256,
+            DigestAlgorithm::Sha384 => &self.sha384,
+            DigestAlgorithm::Sha512 => &self.sha512,
+        }
+    }
+
+    /// The Subresource Integrity metadata of a specific algorithm, such as **sha256-{base64 digest}**.
+    #[inline]
+    pub fn to_sri(&self, algorithm: DigestAlgorithm) -> String {
+        format!("{}-{}", algorithm.sri_name(), STANDARD.encode(self.digest(algorithm)))
+    }
+}
+
+impl Display for Integrity {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_sri(DigestAlgorithm::Sha384))
+    }
+}
+
+/// Feeds the data written to it to every hasher.
+struct Hashers(Sha256, Sha384, Sha512);
+
+impl Write for Hashers {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        self.0.update(buf);
+        self.1.update(buf);
+        self.2.update(buf);
+
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+/// Get the digests computed at compile time, or compute them.
+#[cfg(static_resources_embed)]
+#[inline]
+pub(crate) fn embedded_integrity(data: &[u8], digests: EmbeddedDigests) -> Integrity {
+    match digests {
+        Some((sha256, sha384, sha512)) => Integrity::from_digests(sha256, sha384, sha512),
+        None => Integrity::from_data(data),
+    }
+}
@@ -42,8 +42,32 @@ fn rocket() -> _ {
 }
 ```
 
-* `static_resources_initializer!` is used for including files into your executable binary file. You need to specify each file's name and its path relative to the directory containing the manifest of your package. For instance, the above example uses **favicon** to represent the file **included-static-resources/favicon.ico** and **favicon_png** to represent the file **included-static-resources/favicon.png**. A name cannot be repeating. In order to reduce the compilation time and allow to hot-reload resources, files are compiled into your executable binary file together, only when you are using the **release** profile.
+* `static_resources_initializer!` is used for including files into your executable binary file. You need to specify each file's name and its path relative to the directory containing the manifest of your package. For instance, the above example uses **favicon** to represent the file **included-static-resources/favicon.ico** and **favicon_png** to represent the file **included-static-resources/favicon.png**. A name cannot be repeating, otherwise the compilation fails. In order to reduce the compilation time and allow to hot-reload resources, files are compiled into your executable binary file together, only when you are using the **release** profile.
+* The **release** profile decides the mode only by default. Enable the `embed` feature to compile files into the executable binary file in any profile (e.g. to test the release behavior in the debug profile), or the `filesystem` feature to read files from the file system in any profile. The `ROCKET_INCLUDE_STATIC_RESOURCES_MODE` environment variable (`embed` or `filesystem`) is read at build time and overrides the features. In this document, "the debug profile" means the file system mode and "the release profile" means the embedded mode.
+* A whole directory can be included by `"prefix/" => dir("path/to/directory", include = ["*.html", "*.css"], exclude = ["*.tmp"])` (`include` and `exclude` are optional glob patterns, given in any order, matched against relative paths). Every file in it is named by the prefix followed by its relative path, such as **prefix/css/style.css**. In the debug profile, files added to or removed from the directory are picked up while the server is running.
+* Enable the `archive` feature to include a zip, tar or tar.gz archive by `"prefix/" => archive("path/to/frontend.tar.gz")`. Every file in it is named by the prefix followed by its path in the archive, and its MIME type is guessed from the path. `register_resource_archive` registers an archive from bytes (e.g. `include_bytes!`) and `register_resource_archive_file` from a file, such as one shipped next to the executable binary file. Sidecar files in an archive (e.g. **app.js.br** next to **app.js**) are used as precompressed data instead of resources, like in a directory. In the debug profile, archive files are read again whenever their modification times change.
+* `static_resources!` generates an enum whose variants identify resources, e.g. `Favicon("favicon") => "path/to/favicon.ico"`, so that a typo in a name becomes a compile error. Attach `Resource::fairing()` and pass the variants to `static_response_handler!` or `StaticContextManager::build` instead of names.
+* Each entry of `static_resources_initializer!` can declare its own `Cache-Control` policy, e.g. `"favicon" => "images/favicon.ico" with CachePolicy::new().public().max_age(86400)` (or `dir(...) with ...` for a whole directory). `CachePolicy` supports `public`, `private`, `max_age`, `stale_while_revalidate`, `immutable` and `no_store`. `StaticResponse` emits the policy on `200`, `206` and `304` responses wherever the resource is served. In the debug profile, policies are kept when files are reloaded, and a `dir(...)` policy applies to files added to the directory later as well.
+* In the release profile, an override directory can be set by `static_resources_override_dir` in **Rocket.toml** (or `resources.set_override_directory(...)` in the closure passed to `StaticResponse::fairing`). A file in it whose relative path equals the name of a resource, such as **override/assets/logo.png** for **assets/logo.png**, is served instead of the embedded data with its own ETag, so that assets can be patched without rebuilding. The directory is read again when `StaticContextManager::rescan_overrides` is called or, on Unix, when the process receives `SIGHUP`.
 * `static_response_handler!` is used for quickly creating **GET** route handlers to retrieve static resources.
+* The routes generated by `static_response_handler!` are Rocket sentinels. Rocket refuses to launch with a clear message if they are mounted without the fairing of static resources. A name which is not registered aborts the launch in the release profile, and is only warned about in the debug profile, where the file may be added later. Set `static_resources_allow_unregistered` to `true` in **Rocket.toml** to only warn in the release profile too, e.g. for resources registered by `register_resource` after the launch.
+* Independent sets of resources, such as those shipped by separate modules or library crates, can be kept in namespaces. `static_resources_initializer!(Admin; "logo" => "images/logo.png")` (or `fairing.namespace::<Admin>()`) registers the resources into `StaticContextManager<Admin>`, which is served by `static_response_handler!(Admin; ...)`, `cached_static_response_handler!(Admin; ...)` and `StaticServer::new(...).namespace::<Admin>()`. `StaticContextManager` without a namespace is `StaticContextManager<()>`. `first.merge(second)` merges the resources of the fairing `second` into the namespace of `first`. Attaching two fairings with the same namespace makes Rocket fail to ignite.
+* `StaticContextManager::try_build` returns `Err(Error::NotFound)` for an unknown name instead of panicking like `build`. `Error` is a responder which responds `404 Not Found` or `500 Internal Server Error`, so a handler can return `Result<StaticResponse, Error>`, which is what `static_response_handler!` generates. Failures of registration make the fairing fail to ignite.
+* `StaticServer::new("prefix/")` can be mounted like `rocket::fs::FileServer` to map request paths to resource names without writing a handler for each resource, e.g. `.mount("/static", StaticServer::new("assets/"))` serves **assets/js/app.js** at `/static/js/app.js` and **assets/index.html** at `/static/`. Requests for unknown resources, or with `..` segments, are forwarded, and the rank can be changed by `StaticServer::rank`.
+* `StaticResources` (the release profile), `FileResources` (the debug profile) and `StaticContextManager` implement the `ResourceStore` trait, whose `get_resource`, `get_resource_encoded` and `resource_names` methods behave the same in both profiles. A resource is returned as a `ResourceView` (MIME type, data, ETag, encoding and `Last-Modified` time), so code written against them compiles in both profiles. Both stores also have the same inherent methods with the same bounds, from the registration methods (`register_resource_file`, `register_resource_directory`, `register_resource_static`, `register_resource_archive_file` and so on) to `reload_if_needed`, `override_directory`, `rescan_overrides` and `last_modified`. Methods which a profile has no use for do nothing there. To use the content of a resource in a handler without building a `StaticResponse` (e.g. to inline an SVG into a template or attach a logo to an email), take `&State<StaticContextManager>` as a request guard and call `StaticContextManager::get_resource_async`, which does not block the executor in the debug profile.
+* Resources can also be registered with their data by `register_resource(name, mime, data)`, where the data can be `&'static [u8]`, `Vec<u8>`, `String`, `Arc<Vec<u8>>` or `bytes::Bytes` (with the `bytes` feature), such as a config JSON rendered at startup. `register_resource` and `unregister_resource` take `&self`, so resources can be added, replaced or removed after launch through `StaticContextManager::resources`. In the release profile, the resources are kept in a snapshot which is swapped atomically, so reading them never blocks.
+* `StaticContextManager::fingerprinted_name` returns a cache-busting name derived from the content hash of a resource, such as **assets/js/app.FVkJi7g27qM.js** for **assets/js/app.js**. `StaticServer` resolves fingerprinted names and serves them with `Cache-Control: public, max-age=31536000, immutable`. Requests with outdated fingerprints are redirected to the current ones, or served with `Cache-Control: no-cache` if `StaticServer::outdated_fingerprint(OutdatedFingerprint::Serve)` is used.
+* Enable the `tera` and/or `handlebars` features for template helpers. `TemplateHelpers::new("/static", "assets/")` describes where `StaticServer::new("assets/")` is mounted. Attach `helpers.fairing()` after the fairing of static resources, and call `helpers.register_tera(&mut engines.tera)` or `helpers.register_handlebars(&mut engines.handlebars)` in `rocket_dyn_templates::Template::custom`. Templates can then use `static_url` (the fingerprinted URL of a resource), `static_integrity` (its Subresource Integrity metadata, e.g. **sha384-...**) and `static_inline` (its UTF-8 content), such as `{{ static_url(name="assets/js/app.js") }}` in Tera or `{{static_url "assets/js/app.js"}}` in Handlebars. The output of `static_inline` is not escaped by Tera, and should be written as `{{{static_inline "..."}}}` in Handlebars.
+* Enable the `integrity` feature to keep the SHA-256, SHA-384 and SHA-512 digests of every resource. They are computed at compile time for embedded files, and whenever a file is loaded in the debug profile. `StaticContextManager::integrity` returns them as an `Integrity`, whose `to_sri(DigestAlgorithm::Sha256)` formats the Subresource Integrity metadata (`to_string()` uses SHA-384), and `digest` returns the raw bytes. `resources.set_digest_headers(DigestHeaders::new().repr_digest().content_digest())` in the closure passed to `StaticResponse::fairing` makes responses carry the `Repr-Digest` and `Content-Digest` headers of RFC 9530, with `sha-256` and/or `sha-512`. Streamed resources are sent without them.
+* In the debug profile, resources are checked and reloaded on a blocking thread of the Tokio runtime, so reading files never stalls the executor. Requests read an immutable snapshot of the resources, which is only replaced when something has been reloaded. Whether a resource has changed is checked against the snapshot without any lock (through the file watcher, or by the metadata of its file without the `watch` feature), so requests for unchanged resources never wait for a lock, even while other files are being reloaded. `static_response_handler!` and `StaticServer` use `StaticContextManager::try_build_async`, which should be preferred over `try_build` in async handlers.
+* In the debug profile, files larger than 16 MiB are streamed from the disk on every request instead of being kept in memory, with ETags derived from their sizes and modification times. The threshold can be changed by `resources.set_stream_threshold(...)` in the closure passed to `StaticResponse::fairing`. Streamed resources are not compressed.
+* In the debug profile, a file is reloaded whenever its modification time, size or inode changes, even if the modification time goes backward (e.g. after `git checkout`, `cp -p` or an atomic rename). For file systems with coarse timestamps, `resources.set_content_hash_check(true)` makes the content of files compared as well on every request.
+* In the debug profile, a file which is missing or unreadable (e.g. during a frontend rebuild) does not stop the server. Its last good content keeps being served, or, if it has never been loaded, a page which names the path and the OS error. Other resources are still reloaded, and the resource recovers as soon as the file can be loaded again. `ResourceStore::get_resource` returns `Err(Error::IOError)` for a resource without content.
+* In the debug profile, enable the `watch` feature to watch files through the file system notification of the OS (e.g. inotify) instead of checking their metadata on every request. Only resources whose files have been changed are reloaded.
+* In the debug profile, enable the `live-reload` feature to make open browser tabs reload whenever a registered file changes. The fairing mounts a server-sent events endpoint at `/__static-resources/live-reload` and a small client script is injected into HTML resources. If only CSS files change, the stylesheets are swapped without reloading the page. Nothing of it is compiled in the release profile.
+* Enable the `gzip`, `brotli` and/or `zstd` features (or the `compression` feature for all of them) to serve compressible resources compressed. The best variant is chosen according to the `Accept-Encoding` header of each request, and every variant has its own ETag. Precompressed sidecar files next to a resource (e.g. **app.js.br**) are used instead if they exist. In the release profile, the files embedded by `embed_file!` and `embed_directory!` (and so by the initializer macros, except for archives) are compressed at compile time by the same code as at runtime. Resources registered with their data (e.g. by `register_resource_static` or `register_resource`) and the files in archives are compressed when they are registered, which happens when the server starts for the resources of the initializer macros.
+* Responses support the `Range` and `If-Range` headers, so browsers can seek in embedded videos or resume downloads. Multiple ranges are sent as `multipart/byteranges`.
+* Responses carry `Last-Modified` (the modification time of the file in the debug profile; in the release profile, the modification time of the file at compile time, or `SOURCE_DATE_EPOCH` if it is set (changing it rebuilds the crates which embed files), for embedded files, and the time they are registered for resources registered at runtime; in both profiles, the modification time recorded in the archive for files in archives, so that it is the same on every instance) and support `If-None-Match`, `If-Modified-Since`, `If-Match` and `If-Unmodified-Since`.
 
 See `examples`.
 */
@@ -57,20 +81,57 @@ pub extern crate mime;
 #[doc(hidden)]
 pub extern crate manifest_dir_macros;
 
+#[doc(hidden)]
+pub use functions::{
+    has_repeating_names, EmbeddedDigests, EmbeddedDirectoryFile, EmbeddedFile, EmbeddedVariants,
+};
+#[doc(hidden)]
+pub use guard::{RouteResource, RouteResources};
+#[doc(hidden)]
+pub use rocket_include_static_resources_macros::{directory_options, embed_directory, embed_file};
+
+#[cfg(feature = "archive")]
+mod archive;
+mod cache_policy;
+mod conditional;
+#[cfg(feature = "integrity")]
+mod digest_headers;
+mod encoding;
+mod error;
+mod fingerprint;
 mod functions;
+mod guard;
+#[cfg(feature = "integrity")]
+mod integrity;
 
 mod macros;
+mod range;
+mod resource_store;
+mod server;
+#[cfg(any(feature = "tera", feature = "handlebars"))]
+mod templates;
 
-#[cfg(debug_assertions)]
+#[cfg(not(static_resources_embed))]
 mod debug;
 
-#[cfg(not(debug_assertions))]
+#[cfg(static_resources_embed)]
 mod release;
 
-#[cfg(debug_assertions)]
+pub use cache_policy::CachePolicy;
+#[cfg(not(static_resources_embed))]
 pub use debug::*;
-#[cfg(not(debug_assertions))]
+#[cfg(feature = "integrity")]
+pub use digest_headers::DigestHeaders;
+pub use encoding::Encoding;
+pub use error::Error;
+#[cfg(feature = "integrity")]
+pub use integrity::{DigestAlgorithm, Integrity};
+#[cfg(static_resources_embed)]
 pub use release::*;
+pub use resource_store::{ResourceData, ResourceStore, ResourceView};
 #[cfg(feature = "cache")]
 pub use rocket_cache_response::CacheResponse;
 pub use rocket_etag_if_none_match::{entity_tag::EntityTag, EtagIfNoneMatch};
+pub use server::{OutdatedFingerprint, StaticServer};
+#[cfg(any(feature = "tera", feature = "handlebars"))]
+pub use templates::TemplateHelpers;
@@ -1,57 +1,244 @@
-/// Used for generating a fairing for static resources.
+/// Used for generating a fairing for static resources. Names must be constant expressions (e.g. string literals) and cannot be repeating, or the compilation fails.
+///
+/// Each entry can declare its `Cache-Control` policy by `with` followed by a `CachePolicy`, such as `"favicon" => "images/favicon.ico" with CachePolicy::new().public().max_age(86400)`.
+///
+/// The entries can be preceded by a namespace type and `;`, such as `static_resources_initializer!(Admin; "logo" => "images/logo.png")`, to make the resources managed by `StaticContextManager<Admin>` independently of other sets of resources.
 #[macro_export]
 macro_rules! static_resources_initializer {
-    ( $($name:expr => $path:expr), * $(,)* ) => {
+    ( $namespace:ty ; $($name:expr => $value:tt $(($($args:tt)*))? $(with $cache_policy:expr)?), * $(,)* ) => {
+        $crate::static_resources_initializer!($($name => $value $(($($args)*))? $(with $cache_policy)?),*).namespace::<$namespace>()
+    };
+    ( $($name:expr => $value:tt $(($($args:tt)*))? $(with $cache_policy:expr)?), * $(,)* ) => {
         {
+            const _: () = assert!(!$crate::has_repeating_names(&[$($name),*]), "the names of static resources cannot be repeating");
+
             $crate::StaticResponse::fairing(|resources| {
                 $crate::static_resources_initialize!(
                     resources
-                    $(, $name => $path)*
+                    $(, $name => $value $(($($args)*))? $(with $cache_policy)?)*
                 );
             })
         }
     };
 }
 
-/// Used for quickly creating **GET** route handlers to retrieve static resources.
+/// Used for generating an enum whose variants identify static resources, so that a typo in a name becomes a compile error. Each entry is written as `Variant("name") => path` (or `Variant("prefix/") => dir(...)`, in which case the variant stands for the prefix). The fairing is created by the associated `fairing` function, and the variants can be passed to `StaticContextManager::build` and `static_response_handler!` instead of names.
+///
+/// ```rust,ignore
+/// static_resources! {
+///     pub enum Resource {
+///         Favicon("favicon") => "examples/front-end/images/favicon.ico",
+///         FaviconPng("favicon-png") => "examples/front-end/images/favicon-16.png",
+///     }
+/// }
+///
+/// static_response_handler! {
+///     "/favicon.ico" => favicon => Resource::Favicon,
+///     "/favicon-16.png" => favicon_png => Resource::FaviconPng,
+/// }
+///
+/// rocket::build().attach(Resource::fairing()).mount("/", routes![favicon, favicon_png])
+/// ```
+///
+/// The resources can be moved into a namespace by `Resource::fairing().namespace::<Admin>()`.
+#[macro_export]
+macro_rules! static_resources {
+    ( $(#[$attr:meta])* $vis:vis enum $ident:ident { $($variant:ident($name:literal) => $value:tt $(($($args:tt)*))? $(with $cache_policy:expr)?), * $(,)* } ) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        $vis enum $ident {
+            $($variant,)*
+        }
+
+        impl $ident {
+            /// All the resources.
+            pub const ALL: &'static [$ident] = &[$($ident::$variant),*];
+
+            /// The name of the resource.
+            #[inline]
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $($ident::$variant => $name,)*
+                }
+            }
+
+            /// Create the fairing which registers all the resources.
+            #[inline]
+            pub fn fairing() -> $crate::StaticResponseFairing {
+                $crate::static_resources_initializer!($($name => $value $(($($args)*))? $(with $cache_policy)?),*)
+            }
+        }
+
+        impl AsRef<str> for $ident {
+            #[inline]
+            fn as_ref(&self) -> &str {
+                self.name()
+            }
+        }
+    };
+}
+
+/// Used for quickly creating **GET** route handlers to retrieve static resources. Unknown names respond `404 Not Found`, and I/O failures respond `500 Internal Server Error`.
+///
+/// Rocket refuses to launch if the routes are mounted without the fairing of static resources, or (in the **release** profile) if a name is not registered.
+///
+/// The routes can be preceded by a namespace type and `;`, such as `static_response_handler!(Admin; "/logo.png" => logo => "logo")`, to serve the resources managed by `StaticContextManager<Admin>`.
 #[macro_export]
 macro_rules! static_response_handler {
-    ( $($route:expr => $handler_name:ident => $name:expr), * $(,)* ) => {
+    ( $namespace:ty ; $($route:expr => $handler_name:ident => $name:expr), * $(,)* ) => {
         $(
+            impl $crate::RouteResource for $handler_name {
+                #[inline]
+                fn resource_name() -> String {
+                    AsRef::<str>::as_ref(&$name).to_string()
+                }
+            }
+
             #[get($route)]
-            fn $handler_name(
-                static_resources: &$crate::rocket::State<$crate::StaticContextManager>,
-                etag_if_none_match: $crate::EtagIfNoneMatch,
-            ) -> $crate::StaticResponse {
-                static_resources.build(&etag_if_none_match, $name)
+            async fn $handler_name(
+                static_resources: $crate::RouteResources<'_, $handler_name, $namespace>,
+                etag_if_none_match: $crate::EtagIfNoneMatch<'_>,
+            ) -> Result<$crate::StaticResponse, $crate::Error> {
+                static_resources.try_build_async(&etag_if_none_match, $name).await
             }
         )*
     };
+    ( $($route:expr => $handler_name:ident => $name:expr), * $(,)* ) => {
+        $crate::static_response_handler! {
+            ();
+            $(
+                $route => $handler_name => $name,
+            )*
+        }
+    };
 }
 
 #[cfg(feature = "cache")]
 /// Used for quickly creating **GET** route handlers to retrieve static resources with cache control.
+///
+/// Like `static_response_handler!`, the arguments can be preceded by a namespace type and `;`, such as `cached_static_response_handler!(Admin; 86400; ...)`.
 #[macro_export]
 macro_rules! cached_static_response_handler {
-    ( $max_age:expr, $must_revalidate:expr ; $($route:expr => $handler_name:ident => $name:expr), * $(,)* ) => {
+    ( $namespace:ty ; $max_age:expr, $must_revalidate:expr ; $($route:expr => $handler_name:ident => $name:expr), * $(,)* ) => {
         $(
+            impl $crate::RouteResource for $handler_name {
+                #[inline]
+                fn resource_name() -> String {
+                    AsRef::<str>::as_ref(&$name).to_string()
+                }
+            }
+
             #[get($route)]
-            fn $handler_name(
-                static_resources: &$crate::rocket::State<$crate::StaticContextManager>,
-                etag_if_none_match: $crate::EtagIfNoneMatch,
-            ) -> $crate::CacheResponse<$crate::StaticResponse> {
-                let responder =  static_resources.build(&etag_if_none_match, $name);
+            async fn $handler_name(
+                static_resources: $crate::RouteResources<'_, $handler_name, $namespace>,
+                etag_if_none_match: $crate::EtagIfNoneMatch<'_>,
+            ) -> Result<$crate::CacheResponse<$crate::StaticResponse>, $crate::Error> {
+                let responder = static_resources.try_build_async(&etag_if_none_match, $name).await?;
 
-                $crate::CacheResponse::public_only_release(responder, $max_age, $must_revalidate)
+                Ok($crate::CacheResponse::public_only_release(responder, $max_age, $must_revalidate))
             }
         )*
     };
+    ( $namespace:ty ; $max_age:expr ; $($route:expr => $handler_name:ident => $name:expr), * $(,)* ) => {
+        $crate::cached_static_response_handler! {
+            $namespace; $max_age, false;
+            $(
+                $route => $handler_name => $name,
+            )*
+        }
+    };
+    ( $max_age:expr, $must_revalidate:expr ; $($route:expr => $handler_name:ident => $name:expr), * $(,)* ) => {
+        $crate::cached_static_response_handler! {
+            (); $max_age, $must_revalidate;
+            $(
+                $route => $handler_name => $name,
+            )*
+        }
+    };
     ( $max_age:expr ; $($route:expr => $handler_name:ident => $name:expr), * $(,)* ) => {
         $crate::cached_static_response_handler! {
-            $max_age, false;
+            (); $max_age, false;
             $(
                 $route => $handler_name => $name,
             )*
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        rocket::{
+            self, get,
+            http::{Header, Status},
+            local::blocking::Client,
+            routes, Build, Rocket,
+        },
+        CachePolicy, ResourceStore, StaticContextManager,
+    };
+
+    crate::static_response_handler! {
+        "/favicon.ico" => favicon => "favicon",
+        "/favicon-16.png" => favicon_png => "images/favicon-16.png",
+    }
+
+    fn rocket() -> Rocket<Build> {
+        rocket::custom(rocket::Config::figment().merge(("log_level", "off")))
+    }
+
+    fn resource_names(fairing: crate::StaticResponseFairing) -> Vec<String> {
+        let client = Client::debug(rocket().attach(fairing)).unwrap();
+
+        let mut names = client.rocket().state::<StaticContextManager>().unwrap().resource_names();
+
+        names.sort();
+
+        names
+    }
+
+    #[test]
+    fn dir_accepts_include_and_exclude_in_any_order() {
+        let expected = vec!["images/favicon.ico".to_string()];
+
+        assert_eq!(
+            expected,
+            resource_names(crate::static_resources_initializer!(
+                "images/" => dir("examples/front-end/images", include = ["*.ico", "*.png"], exclude = ["*.png"]),
+            ))
+        );
+        assert_eq!(
+            expected,
+            resource_names(crate::static_resources_initializer!(
+                "images/" => dir("examples/front-end/images", exclude = ["*.png"], include = ["*.ico", "*.png"]),
+            ))
+        );
+    }
+
+    #[test]
+    fn cache_policies_are_sent_with_200_and_304() {
+        let rocket = rocket()
+            .attach(crate::static_resources_initializer!(
+                "favicon" => "examples/front-end/images/favicon.ico" with CachePolicy::new().public().max_age(86400),
+                "images/" => dir("examples/front-end/images", include = ["*.png"]) with CachePolicy::new().no_store(),
+            ))
+            .mount("/", routes![favicon, favicon_png]);
+
+        let client = Client::debug(rocket).unwrap();
+
+        for (uri, cache_control) in
+            [("/favicon.ico", "public, max-age=86400"), ("/favicon-16.png", "no-store")]
+        {
+            let response = client.get(uri).dispatch();
+
+            assert_eq!(Status::Ok, response.status());
+            assert_eq!(Some(cache_control), response.headers().get_one("Cache-Control"));
+
+            let etag = response.headers().get_one("ETag").unwrap().to_string();
+
+            let response = client.get(uri).header(Header::new("If-None-Match", etag)).dispatch();
+
+            assert_eq!(Status::NotModified, response.status());
+            assert_eq!(Some(cache_control), response.headers().get_one("Cache-Control"));
+        }
+    }
+}
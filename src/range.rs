@@ -0,0 +1,244 @@
+use std::{
+    io::Cursor,
+    ops::Range,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    conditional::if_range_matches,
+    rocket::{
+        http::Status,
+        request::Request,
+        response::{self},
+    },
+    EntityTag,
+};
+
+/// The maximum number of ranges in a `Range` header. Requests with more ranges get the whole representation.
+const MAX_RANGES: usize = 32;
+
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRanges {
+    All,
+    Satisfiable(Vec<Range<usize>>),
+    Unsatisfiable,
+}
+
+/// Parse a `Range` header. Unknown units and invalid syntax make the header ignored.
+fn parse_range(range: &str, length: usize) -> ByteRanges {
+    let range_set = match range.trim().split_once('=') {
+        Some((unit, range_set)) if unit.trim().eq_ignore_ascii_case("bytes") => range_set,
+        _ => return ByteRanges::All,
+    };
+
+    let mut ranges = Vec::new();
+
+    for spec in range_set.split(',') {
+        let spec = spec.trim();
+
+        if spec.is_empty() {
+            continue;
+        }
+
+        let (first, last) = match spec.split_once('-') {
+            Some(pair) => pair,
+            None => return ByteRanges::All,
+        };
+
+        let (first, last) = (first.trim(), last.trim());
+
+        if first.is_empty() {
+            // suffix-byte-range-spec
+            let suffix_length = match last.parse::<usize>() {
+                Ok(suffix_length) => suffix_length,
+                Err(_) => return ByteRanges::All,
+            };
+
+            if suffix_length > 0 && length > 0 {
+                ranges.push(length.saturating_sub(suffix_length)..length);
+            }
+        } else {
+            let first = match first.parse::<usize>() {
+                Ok(first) => first,
+                Err(_) => return ByteRanges::All,
+            };
+
+            let last = if last.is_empty() {
+                usize::MAX
+            } else {
+                match last.parse::<usize>() {
+                    Ok(last) if last >= first => last,
+                    _ => return ByteRanges::All,
+                }
+            };
+
+            if first < length {
+                ranges.push(first..last.saturating_add(1).min(length));
+            }
+        }
+
+        if ranges.len() > MAX_RANGES {
+            return ByteRanges::All;
+        }
+    }
+
+    if ranges.is_empty() {
+        ByteRanges::Unsatisfiable
+    } else {
+        ByteRanges::Satisfiable(ranges)
+    }
+}
+
+#[inline]
+fn content_range(range: &Range<usize>, length: usize) -> String {
+    format!("bytes {}-{}/{}", range.start, range.end - 1, length)
+}
+
+#[inline]
+fn multipart_boundary(etag: &EntityTag<'static>) -> String {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+
+    let tag: String = etag.get_tag().chars().filter(char::is_ascii_alphanumeric).collect();
+
+    format!("static-resource-{}-{:08x}", tag, nanos)
+}
+
+/// Handle the `Range` and `If-Range` headers of a request for a representation of `length` bytes.
+///
+/// It sets the status and the range related headers. If a single part of the representation needs to be sent, its range is returned and the body should be set by the caller. Otherwise, the body is set here and `None` is returned. `data` is `None` if the representation is streamed instead of being in memory, in which case multiple ranges are not supported and the whole representation is sent.
+pub(crate) fn apply_range<'o>(
+    response: &mut response::Builder<'o>,
+    request: &Request<'_>,
+    mime: &str,
+    etag: &EntityTag<'static>,
+    last_modified: Option<SystemTime>,
+    length: usize,
+    data: Option<&[u8]>,
+) -> Option<Range<usize>> {
+    response.raw_header("Accept-Ranges", "bytes");
+
+    let ranges = match request.headers().get_one("Range") {
+        Some(range) if if_range_matches(request, etag, last_modified) => parse_range(range, length),
+        _ => ByteRanges::All,
+    };
+
+    let ranges = match (ranges, data) {
+        (ByteRanges::Satisfiable(ranges), None) if ranges.len() > 1 => ByteRanges::All,
+        (ranges, _) => ranges,
+    };
+
+    match ranges {
+        ByteRanges::All => {
+            response.raw_header("Content-Type", mime.to_string());
+
+            Some(0..length)
+        },
+        ByteRanges::Unsatisfiable => {
+            response.status(Status::RangeNotSatisfiable);
+            response.raw_header("Content-Range", format!("bytes */{}", length));
+            response.sized_body(0, Cursor::new(Vec::new()));
+
+            None
+        },
+        ByteRanges::Satisfiable(mut ranges) => {
+            response.status(Status::PartialContent);
+
+            if ranges.len() == 1 {
+                let range = ranges.remove(0);
+
+                response.raw_header("Content-Type", mime.to_string());
+                response.raw_header("Content-Range", content_range(&range, length));
+
+                Some(range)
+            } else {
+                // multiple ranges of streamed representations have been turned into `All`
+                let data = data.unwrap_or_default();
+
+                let boundary = multipart_boundary(etag);
+
+                let mut body = Vec::new();
+
+                for range in ranges {
+                    body.extend_from_slice(b"\r\n--");
+                    body.extend_from_slice(boundary.as_bytes());
+                    body.extend_from_slice(b"\r\nContent-Type: ");
+                    body.extend_from_slice(mime.as_bytes());
+                    body.extend_from_slice(b"\r\nContent-Range: ");
+                    body.extend_from_slice(content_range(&range, length).as_bytes());
+                    body.extend_from_slice(b"\r\n\r\n");
+                    body.extend_from_slice(&data[range]);
+                }
+
+                body.extend_from_slice(b"\r\n--");
+                body.extend_from_slice(boundary.as_bytes());
+                body.extend_from_slice(b"--\r\n");
+
+                response.raw_header(
+                    "Content-Type",
+                    format!("multipart/byteranges; boundary={}", boundary),
+                );
+                response.sized_body(body.len(), Cursor::new(body));
+
+                None
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn satisfiable(ranges: &[(usize, usize)]) -> ByteRanges {
+        ByteRanges::Satisfiable(ranges.iter().map(|&(start, end)| start..end).collect())
+    }
+
+    #[test]
+    fn suffix_ranges() {
+        assert_eq!(satisfiable(&[(5, 10)]), parse_range("bytes=-5", 10));
+        // longer than the body
+        assert_eq!(satisfiable(&[(0, 10)]), parse_range("bytes=-20", 10));
+        assert_eq!(ByteRanges::Unsatisfiable, parse_range("bytes=-0", 10));
+        assert_eq!(ByteRanges::Unsatisfiable, parse_range("bytes=-5", 0));
+    }
+
+    #[test]
+    fn open_ranges() {
+        assert_eq!(satisfiable(&[(5, 10)]), parse_range("bytes=5-", 10));
+        // the first byte is out of a short body
+        assert_eq!(ByteRanges::Unsatisfiable, parse_range("bytes=5-", 3));
+        assert_eq!(ByteRanges::Unsatisfiable, parse_range("bytes=5-", 5));
+        // the last byte is clamped to the body
+        assert_eq!(satisfiable(&[(0, 10)]), parse_range("bytes=0-99", 10));
+    }
+
+    #[test]
+    fn multiple_ranges() {
+        assert_eq!(satisfiable(&[(0, 2), (4, 6), (8, 10)]), parse_range("bytes=0-1, 4-5,,-2", 10));
+        // unsatisfiable ranges are dropped if any range is satisfiable
+        assert_eq!(satisfiable(&[(0, 2)]), parse_range("bytes=0-1, 20-30", 10));
+    }
+
+    #[test]
+    fn too_many_ranges() {
+        let range_set = |count: usize| {
+            let specs = (0..count).map(|i| format!("{}-{}", i * 2, i * 2)).collect::<Vec<_>>();
+
+            format!("bytes={}", specs.join(","))
+        };
+
+        assert!(matches!(
+            parse_range(&range_set(MAX_RANGES), 1000),
+            ByteRanges::Satisfiable(ranges) if ranges.len() == MAX_RANGES
+        ));
+        assert_eq!(ByteRanges::All, parse_range(&range_set(MAX_RANGES + 1), 1000));
+    }
+
+    #[test]
+    fn ignored_headers() {
+        assert_eq!(ByteRanges::All, parse_range("items=0-1", 10));
+        assert_eq!(ByteRanges::All, parse_range("bytes=5-2", 10));
+        assert_eq!(ByteRanges::All, parse_range("bytes=abc", 10));
+        assert_eq!(ByteRanges::All, parse_range("bytes=1-x", 10));
+    }
+}
@@ -1,45 +1,175 @@
+use std::{any::type_name, marker::PhantomData, path::PathBuf};
+
 use super::{StaticContextManager, StaticResources, StaticResponse};
 use crate::rocket::{
     fairing::{Fairing, Info, Kind},
-    Build, Rocket,
+    Build, Orbit, Rocket,
 };
 
 const FAIRING_NAME: &str = "Static Resources";
 
-/// The fairing of `StaticResponse`.
-pub struct StaticResponseFairing {
+/// The parameter in **Rocket.toml** which sets the override directory.
+const OVERRIDE_DIR_KEY: &str = "static_resources_override_dir";
+
+/// The fairing of `StaticResponse`. It registers the resources into `StaticContextManager<N>`, where `N` is the namespace of the resources.
+pub struct StaticResponseFairing<N = ()> {
     pub(crate) custom_callback: Box<dyn Fn(&mut StaticResources) + Send + Sync + 'static>,
+    namespace:                  PhantomData<fn() -> N>,
+}
+
+impl<N> StaticResponseFairing<N> {
+    /// Move the resources into the namespace `M`, so that they are managed by `StaticContextManager<M>` independently of other sets of resources.
+    #[inline]
+    pub fn namespace<M>(self) -> StaticResponseFairing<M> {
+        StaticResponseFairing {
+            custom_callback: self.custom_callback,
+            namespace:       PhantomData,
+        }
+    }
+
+    /// Merge the resources registered by another fairing into the namespace of this one, so that both sets of resources are managed by one `StaticContextManager<N>`.
+    #[inline]
+    pub fn merge<M>(self, other: StaticResponseFairing<M>) -> StaticResponseFairing<N> {
+        let (first, second) = (self.custom_callback, other.custom_callback);
+
+        StaticResponseFairing {
+            custom_callback: Box::new(move |resources| {
+                first(resources);
+                second(resources);
+            }),
+            namespace:       PhantomData,
+        }
+    }
 }
 
 #[rocket::async_trait]
-impl Fairing for StaticResponseFairing {
+impl<N: 'static> Fairing for StaticResponseFairing<N> {
     #[inline]
     fn info(&self) -> Info {
         Info {
-            name: FAIRING_NAME, kind: Kind::Ignite
+            name: FAIRING_NAME, kind: Kind::Ignite | Kind::Liftoff
         }
     }
 
     #[inline]
     async fn on_ignite(&self, rocket: Rocket<Build>) -> Result<Rocket<Build>, Rocket<Build>> {
+        if rocket.state::<StaticContextManager<N>>().is_some() {
+            rocket::error!(
+                "The static resources of the namespace `{}` are registered by more than one \
+                 fairing. Merge the fairings by `StaticResponseFairing::merge`, or move them into \
+                 different namespaces by `StaticResponseFairing::namespace`.",
+                type_name::<N>()
+            );
+
+            return Err(rocket);
+        }
+
         let mut resources = StaticResources::new();
 
         (self.custom_callback)(&mut resources);
 
-        let state = StaticContextManager::new(resources);
+        let errors = resources.take_errors();
+
+        if !errors.is_empty() {
+            for (name, err) in errors {
+                rocket::error!("Failed to register the static resource `{}`: {}", name, err);
+            }
+
+            return Err(rocket);
+        }
+
+        match rocket.figment().extract_inner::<PathBuf>(OVERRIDE_DIR_KEY) {
+            Ok(directory) => resources.set_override_directory(directory),
+            Err(err) if err.missing() => (),
+            Err(err) => {
+                rocket::error!("Invalid `{}`: {}", OVERRIDE_DIR_KEY, err);
+
+                return Err(rocket);
+            },
+        }
+
+        if let Err(err) = resources.rescan_overrides() {
+            rocket::error!(
+                "Failed to read the override directory {:?}: {}",
+                resources.override_directory(),
+                err
+            );
+
+            return Err(rocket);
+        }
+
+        let state = StaticContextManager::<N>::new(resources);
 
         Ok(rocket.manage(state))
     }
+
+    /// Read the override directory again whenever the process receives `SIGHUP`.
+    #[cfg(unix)]
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        use crate::rocket::tokio::{
+            select,
+            signal::unix::{signal, SignalKind},
+            task,
+        };
+
+        let overrides = match rocket
+            .state::<StaticContextManager<N>>()
+            .and_then(|state| state.resources.overrides())
+        {
+            Some(overrides) => overrides.clone(),
+            None => return,
+        };
+
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(err) => {
+                rocket::warn!(
+                    "Failed to listen for SIGHUP, so the override directory can only be read \
+                     again by `StaticContextManager::rescan_overrides`: {}",
+                    err
+                );
+
+                return;
+            },
+        };
+
+        let mut shutdown = rocket.shutdown();
+
+        rocket::tokio::spawn(async move {
+            loop {
+                select! {
+                    signal = hangup.recv() => if signal.is_none() { break },
+                    _ = &mut shutdown => break,
+                }
+
+                let overrides = overrides.clone();
+
+                match task::spawn_blocking(move || overrides.rescan()).await {
+                    Ok(Ok(count)) => {
+                        rocket::info!("The override directory has {} files.", count)
+                    },
+                    Ok(Err(err)) => {
+                        rocket::error!("Failed to read the override directory: {}", err)
+                    },
+                    Err(err) => rocket::error!("Failed to read the override directory: {}", err),
+                }
+            }
+        });
+    }
+
+    #[cfg(not(unix))]
+    #[inline]
+    async fn on_liftoff(&self, _rocket: &Rocket<Orbit>) {}
 }
 
 impl StaticResponse {
     #[inline]
     /// Create the fairing of `HandlebarsResponse`.
-    pub fn fairing<F>(f: F) -> impl Fairing
+    pub fn fairing<F>(f: F) -> StaticResponseFairing
     where
         F: Fn(&mut StaticResources) + Send + Sync + 'static, {
         StaticResponseFairing {
-            custom_callback: Box::new(f)
+            custom_callback: Box::new(f), namespace: PhantomData
         }
     }
 }
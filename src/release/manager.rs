@@ -1,36 +1,159 @@
+use std::{io, marker::PhantomData};
+
 use super::StaticResources;
-use crate::{EtagIfNoneMatch, StaticResponse};
+#[cfg(feature = "integrity")]
+use crate::Integrity;
+use crate::{
+    encoding::Encoding,
+    fingerprint::{fingerprint, fingerprinted_name},
+    Error, EtagIfNoneMatch, ResourceStore, ResourceView, StaticResponse,
+};
 
-/// To monitor the state of static resources.
+/// To monitor the state of static resources. `N` is the namespace of the resources (see `StaticResponseFairing::namespace`), so that independent sets of resources can be managed at the same time, such as `StaticContextManager<Admin>`.
 #[derive(Debug)]
-pub struct StaticContextManager {
+pub struct StaticContextManager<N = ()> {
     pub resources: StaticResources,
+    namespace:     PhantomData<fn() -> N>,
 }
 
-impl StaticContextManager {
+impl<N> StaticContextManager<N> {
     #[inline]
-    pub(crate) fn new(resources: StaticResources) -> StaticContextManager {
+    pub(crate) fn new(resources: StaticResources) -> StaticContextManager<N> {
         StaticContextManager {
             resources,
+            namespace: PhantomData,
         }
     }
 
+    /// Build a `StaticResponse`. The response chooses the best encoded variant of the resource according to the `Accept-Encoding` header of the request.
+    ///
+    /// Returns `Err(Error::NotFound)` if the name is not registered.
+    #[inline]
+    pub fn try_build<S: AsRef<str>>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        name: S,
+    ) -> Result<StaticResponse, Error> {
+        let name = name.as_ref();
+
+        let resource = self.resources.get(name).ok_or_else(|| Error::NotFound(name.to_string()))?;
+
+        let response = match self.resources.get_override(name) {
+            Some(file) => StaticResponse::build_override(&resource, file, etag_if_none_match),
+            None => StaticResponse::build(&resource, etag_if_none_match),
+        };
+
+        #[cfg(feature = "integrity")]
+        let response = response.with_digest_headers(self.resources.digest_headers());
+
+        Ok(response)
+    }
+
+    /// Build a `StaticResponse` like `try_build`. Resources are embedded in the release profile, so it never blocks. It exists so that code can use it in both profiles.
+    #[inline]
+    pub async fn try_build_async<S: AsRef<str>>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        name: S,
+    ) -> Result<StaticResponse, Error> {
+        self.try_build(etag_if_none_match, name)
+    }
+
+    /// Get the data, MIME type and ETag of a resource without building a `StaticResponse`, such as to inline an SVG into a template or attach a logo to an email. The view is the same in the debug and release profiles, and stays valid even if the resource is reloaded later.
+    ///
+    /// It behaves like `ResourceStore::get_resource`, returning `Err(Error::NotFound)` if the name is not registered.
+    #[inline]
+    pub async fn get_resource_async<S: AsRef<str>>(&self, name: S) -> Result<ResourceView, Error> {
+        self.resources.get_resource(name.as_ref())
+    }
+
+    /// Get the digests of a resource, such as for the `integrity` attribute of `<script>` elements. They are computed at compile time for embedded files. If the resource is overridden, the digests of the overriding file are returned.
+    ///
+    /// Returns `Err(Error::NotFound)` if the name is not registered.
+    #[cfg(feature = "integrity")]
+    #[inline]
+    pub fn integrity<S: AsRef<str>>(&self, name: S) -> Result<Integrity, Error> {
+        let name = name.as_ref();
+
+        let resource = self.resources.get(name).ok_or_else(|| Error::NotFound(name.to_string()))?;
+
+        Ok(match self.resources.get_override(name) {
+            Some(file) => file.integrity,
+            None => resource.integrity.clone(),
+        })
+    }
+
+    /// Get another handle of the same resources.
+    #[cfg(any(feature = "tera", feature = "handlebars"))]
+    #[inline]
+    pub(crate) fn share(&self) -> StaticContextManager<N> {
+        StaticContextManager::new(self.resources.share())
+    }
+
+    /// Whether the name is registered.
+    #[inline]
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.resources.get(name).is_some()
+    }
+
+    /// Get the fingerprint of a resource like `fingerprint`.
+    #[inline]
+    pub(crate) async fn fingerprint_async(&self, name: &str) -> Result<String, Error> {
+        self.fingerprint(name)
+    }
+
+    /// Get the fingerprint of a resource, which is derived from its content.
+    #[inline]
+    pub(crate) fn fingerprint<S: AsRef<str>>(&self, name: S) -> Result<String, Error> {
+        self.resources.get_resource(name.as_ref()).map(|resource| fingerprint(resource.etag()))
+    }
+
+    /// Get the fingerprinted name of a resource, such as **js/app.{fingerprint}.js** for **js/app.js**. The fingerprint is derived from the content of the resource, so the name changes whenever the content changes and can be cached forever. `StaticServer` serves fingerprinted names with `Cache-Control: public, max-age=31536000, immutable`.
+    #[inline]
+    pub fn fingerprinted_name<S: AsRef<str>>(&self, name: S) -> Result<String, Error> {
+        let name = name.as_ref();
+
+        self.fingerprint(name).map(|fingerprint| fingerprinted_name(name, &fingerprint))
+    }
+
+    /// Read the override directory again and return the number of files in it. See `StaticResources::set_override_directory`.
+    #[inline]
+    pub fn rescan_overrides(&self) -> Result<usize, io::Error> {
+        self.resources.rescan_overrides()
+    }
+
     /// Build a `StaticResponse`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name is not registered. Use `try_build` to handle the error instead.
     #[inline]
     pub fn build<S: AsRef<str>>(
         &self,
         etag_if_none_match: &EtagIfNoneMatch<'_>,
         name: S,
     ) -> StaticResponse {
-        self.resources
-            .get_resource(name.as_ref())
-            .map(|resource| {
-                if etag_if_none_match.weak_eq(resource.2) {
-                    StaticResponse::not_modified()
-                } else {
-                    StaticResponse::build(&resource.0, resource.1, resource.2)
-                }
-            })
-            .unwrap()
+        self.try_build(etag_if_none_match, name).unwrap()
+    }
+}
+
+impl<N> ResourceStore for StaticContextManager<N> {
+    #[inline]
+    fn get_resource(&self, name: &str) -> Result<ResourceView, Error> {
+        self.resources.get_resource(name)
+    }
+
+    #[inline]
+    fn get_resource_encoded(
+        &self,
+        name: &str,
+        encoding: Encoding,
+    ) -> Result<Option<ResourceView>, Error> {
+        self.resources.get_resource_encoded(name, encoding)
+    }
+
+    #[inline]
+    fn resource_names(&self) -> Vec<String> {
+        self.resources.resource_names()
     }
 }
@@ -1,3 +1,4 @@
+mod overrides;
 mod static_resources;
 mod static_response;
 
@@ -0,0 +1,84 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use arc_swap::ArcSwap;
+
+#[cfg(feature = "integrity")]
+use crate::Integrity;
+use crate::{
+    functions::{collect_files, compute_data_etag},
+    EntityTag,
+};
+
+/// A file which replaces the embedded data of the resource with the same name.
+#[derive(Debug, Clone)]
+pub(crate) struct OverrideFile {
+    pub(crate) data:          Arc<Vec<u8>>,
+    pub(crate) etag:          EntityTag<'static>,
+    pub(crate) last_modified: Option<SystemTime>,
+    #[cfg(feature = "integrity")]
+    pub(crate) integrity:     Integrity,
+}
+
+/// The files in an override directory, named by their paths relative to the directory (separated by `/`).
+#[derive(Debug)]
+pub(crate) struct Overrides {
+    directory: PathBuf,
+    files:     ArcSwap<HashMap<String, OverrideFile>>,
+}
+
+impl Overrides {
+    #[inline]
+    pub(crate) fn new(directory: PathBuf) -> Overrides {
+        Overrides {
+            directory,
+            files: ArcSwap::default(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, name: &str) -> Option<OverrideFile> {
+        self.files.load().get(name).cloned()
+    }
+
+    /// Read the files in the directory again and return how many there are. A missing directory has no files. If any file cannot be read, the previous files are kept.
+    pub(crate) fn rescan(&self) -> Result<usize, io::Error> {
+        let mut paths = Vec::new();
+
+        collect_files(&self.directory, &self.directory, &mut paths);
+
+        let mut files = HashMap::with_capacity(paths.len());
+
+        for (name, path) in paths {
+            let data = fs::read(&path)?;
+
+            let last_modified = path.metadata().and_then(|metadata| metadata.modified()).ok();
+
+            let file = OverrideFile {
+                etag: compute_data_etag(&data),
+                #[cfg(feature = "integrity")]
+                integrity: Integrity::from_data(&data),
+                data: Arc::new(data),
+                last_modified,
+            };
+
+            files.insert(name, file);
+        }
+
+        let count = files.len();
+
+        self.files.store(Arc::new(files));
+
+        Ok(count)
+    }
+}
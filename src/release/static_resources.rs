@@ -1,30 +1,127 @@
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
 
-use crate::{functions::compute_data_etag, mime::Mime, EntityTag};
+use arc_swap::ArcSwap;
 
-#[derive(Debug)]
-struct Resource {
-    mime: Mime,
-    data: &'static [u8],
-    etag: EntityTag<'static>,
+use super::overrides::{OverrideFile, Overrides};
+#[cfg(feature = "archive")]
+use crate::archive::read_archive;
+use crate::{
+    encoding::{is_compressible, Encoding},
+    functions::{
+        build_time, collect_files, compile_patterns, compute_data_etag, embedded_last_modified,
+        is_matched,
+    },
+    mime::Mime,
+    CachePolicy, EmbeddedDigests, EmbeddedDirectoryFile, EmbeddedFile, EmbeddedVariants, EntityTag,
+    Error, ResourceData, ResourceStore, ResourceView,
+};
+#[cfg(feature = "integrity")]
+use crate::{integrity::embedded_integrity, DigestHeaders, Integrity};
+
+#[derive(Debug, Clone)]
+pub(crate) struct EncodedResource {
+    pub(crate) encoding:  Encoding,
+    pub(crate) data:      ResourceData,
+    pub(crate) etag:      EntityTag<'static>,
+    #[cfg(feature = "integrity")]
+    pub(crate) integrity: Integrity,
 }
 
+#[derive(Debug, Clone)]
+pub(crate) struct Resource {
+    pub(crate) mime:          Mime,
+    pub(crate) data:          ResourceData,
+    pub(crate) etag:          EntityTag<'static>,
+    pub(crate) encoded:       Vec<EncodedResource>,
+    pub(crate) cache_policy:  CachePolicy,
+    pub(crate) last_modified: SystemTime,
+    /// The file which the resource was read from by `register_resource_file` or `register_resource_directory`.
+    path:                     Option<PathBuf>,
+    #[cfg(feature = "integrity")]
+    pub(crate) integrity:     Integrity,
+}
+
+type Resources = HashMap<Cow<'static, str>, Arc<Resource>>;
+
 #[derive(Debug)]
 /// Static resources.
+///
+/// The resources are kept in a snapshot which is replaced as a whole when a resource is registered or unregistered at runtime, so reading them never blocks.
 pub struct StaticResources {
-    resources: HashMap<&'static str, Resource>,
+    resources:      Arc<ArcSwap<Resources>>,
+    last_modified:  SystemTime,
+    overrides:      Option<Arc<Overrides>>,
+    #[cfg(feature = "integrity")]
+    digest_headers: DigestHeaders,
+    errors:         Vec<(String, io::Error)>,
 }
 
 impl StaticResources {
     /// Create an instance of `StaticResources`.
+    ///
+    /// Embedded files have their modification times at compile time as their `Last-Modified` times. Resources registered by `register_resource_static` have the time this crate was built instead, which is the `SOURCE_DATE_EPOCH` environment variable at compile time if it is set, so that builds stay reproducible.
     #[inline]
     pub fn new() -> StaticResources {
         StaticResources {
-            resources: HashMap::new()
+            resources:                                    Arc::default(),
+            last_modified:                                build_time(),
+            overrides:                                    None,
+            #[cfg(feature = "integrity")]
+            digest_headers:                               DigestHeaders::new(),
+            errors:                                       Vec::new(),
         }
     }
 
-    /// Register a static resource.
+    /// Report that the resource (or the archive) named `name` failed to be registered. The fairing fails to ignite if any error is reported.
+    #[inline]
+    pub fn report_error<S: Into<String>>(&mut self, name: S, error: io::Error) {
+        self.errors.push((name.into(), error));
+    }
+
+    /// Get another handle of the same resources.
+    #[cfg(any(feature = "tera", feature = "handlebars"))]
+    #[inline]
+    pub(crate) fn share(&self) -> StaticResources {
+        #[cfg(feature = "integrity")]
+        let digest_headers = self.digest_headers;
+
+        StaticResources {
+            resources: self.resources.clone(),
+            last_modified: self.last_modified,
+            overrides: self.overrides.clone(),
+            #[cfg(feature = "integrity")]
+            digest_headers,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Take the reported errors.
+    #[inline]
+    pub(crate) fn take_errors(&mut self) -> Vec<(String, io::Error)> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Modify the resources in place. They are only cloned if they are being read, which does not happen before the fairing ignites.
+    #[inline]
+    fn modify<T>(&mut self, f: impl FnOnce(&mut Resources) -> T) -> T {
+        let mut resources = self.resources.swap(Arc::default());
+
+        let result = f(Arc::make_mut(&mut resources));
+
+        self.resources.store(resources);
+
+        result
+    }
+
+    /// Register a static resource. If the data is compressible, it is also compressed with every enabled encoding.
     #[inline]
     pub fn register_resource_static(
         &mut self,
@@ -32,26 +129,522 @@ impl StaticResources {
         mime: Mime,
         data: &'static [u8],
     ) {
-        let etag = compute_data_etag(data);
+        let resource =
+            Arc::new(build_resource(mime, ResourceData::Static(data), self.last_modified));
+
+        self.modify(|resources| resources.insert(Cow::Borrowed(name), resource));
+    }
+
+    /// Register a static resource embedded by `embed_file!`, whose encoded variants and digests may have been computed at compile time.
+    #[doc(hidden)]
+    #[inline]
+    pub fn register_resource_embedded(
+        &mut self,
+        name: &'static str,
+        mime: Mime,
+        (data, digests, variants, modified): EmbeddedFile,
+    ) {
+        let last_modified = embedded_last_modified(modified, self.last_modified);
+
+        let resource =
+            Arc::new(build_embedded_resource(mime, data, digests, variants, last_modified));
+
+        self.modify(|resources| resources.insert(Cow::Borrowed(name), resource));
+    }
+
+    /// Register a resource from a file, which is read now instead of being embedded. It is never reloaded, because files are only reloaded in the debug profile. Its `Last-Modified` time is the modification time of the file. Precompressed sidecar files next to it (`.br`, `.zst` and `.gz`) are used for encoded responses. Otherwise, if the data is compressible, it is compressed with every enabled encoding.
+    #[inline]
+    pub fn register_resource_file<P: Into<PathBuf>>(
+        &mut self,
+        name: &'static str,
+        file_path: P,
+    ) -> Result<(), io::Error> {
+        let resource = Arc::new(read_resource_file(file_path.into())?);
+
+        self.modify(|resources| resources.insert(Cow::Borrowed(name), resource));
+
+        Ok(())
+    }
+
+    /// Register the files in a directory as resources like `register_resource_file`. Each file is named by `prefix` followed by its relative path (separated by `/`). Only files matched by any `include` glob pattern (if there is any) and not matched by any `exclude` glob pattern are registered. Sidecar files (e.g. **app.js.br** next to **app.js**) are used as precompressed data instead of resources.
+    ///
+    /// The directory is only read once, so files added to or removed from it later are not picked up.
+    pub fn register_resource_directory<S: Into<String>, P: Into<PathBuf>>(
+        &mut self,
+        prefix: S,
+        directory_path: P,
+        include: &[&str],
+        exclude: &[&str],
+    ) -> Result<(), io::Error> {
+        let prefix = prefix.into();
+        let path = directory_path.into();
+
+        let (include, exclude) = (compile_patterns(include)?, compile_patterns(exclude)?);
+
+        if !path.is_dir() {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("{:?} is not a directory.", path),
+            ));
+        }
+
+        let mut files = Vec::new();
 
-        let resource = Resource {
-            mime,
-            data,
-            etag,
+        collect_files(&path, &path, &mut files);
+
+        files.retain(|(relative_path, _)| is_matched(relative_path, &include, &exclude));
+
+        let is_sidecar = |relative_path: &str| match Encoding::split_sidecar_path(relative_path) {
+            Some((original_path, _)) => files.iter().any(|(r, _)| r == original_path),
+            None => false,
         };
 
-        self.resources.insert(name, resource);
+        let mut directory_resources = Vec::with_capacity(files.len());
+
+        for (relative_path, path) in files.iter() {
+            if !is_sidecar(relative_path) {
+                directory_resources.push((
+                    Cow::Owned(format!("{}{}", prefix, relative_path)),
+                    Arc::new(read_resource_file(path.clone())?),
+                ));
+            }
+        }
+
+        self.modify(|resources| resources.extend(directory_resources));
+
+        Ok(())
     }
 
-    /// Get the specific resource.
+    /// Unregister a resource by a name. Returns the path of its file if it was registered by `register_resource_file` or `register_resource_directory`.
     #[inline]
-    pub fn get_resource<S: AsRef<str>>(
+    pub fn unregister_resource_file<S: AsRef<str>>(&mut self, name: S) -> Option<PathBuf> {
+        self.modify(|resources| resources.remove(name.as_ref()).and_then(|r| r.path.clone()))
+    }
+
+    /// Register a resource whose data is owned or shared, such as data generated at startup. If the name is registered, the resource is replaced but keeps its `Cache-Control` policy. If the data is compressible, it is also compressed with every enabled encoding.
+    ///
+    /// It can be called at any time, e.g. through `StaticContextManager::resources` after the server is launched. Requests being served keep the resource they have got.
+    pub fn register_resource<S: Into<Cow<'static, str>>, D: Into<ResourceData>>(
         &self,
         name: S,
-    ) -> Option<(&Mime, &'static [u8], &EntityTag<'static>)> {
+        mime: Mime,
+        data: D,
+    ) {
+        let name = name.into();
+
+        let resource = build_resource(mime, data.into(), SystemTime::now());
+
+        self.resources.rcu(|resources| {
+            let mut resources = HashMap::clone(resources);
+
+            let cache_policy =
+                resources.get(&name).map(|resource| resource.cache_policy).unwrap_or_default();
+
+            resources.insert(
+                name.clone(),
+                Arc::new(Resource {
+                    cache_policy,
+                    ..resource.clone()
+                }),
+            );
+
+            resources
+        });
+    }
+
+    /// Register the files in a zip, tar or tar.gz archive (e.g. embedded by `include_bytes!`) as resources. Each file is named by `prefix` followed by its path in the archive (separated by `/`), and its MIME type is guessed from the path. Its `Last-Modified` time is its modification time in the archive, or `last_modified` if there is none. Sidecar files in the archive (e.g. **app.js.br** next to **app.js**) are used as precompressed data instead of resources.
+    #[cfg(feature = "archive")]
+    pub fn register_resource_archive<S: AsRef<str>>(
+        &mut self,
+        prefix: S,
+        archive: &[u8],
+    ) -> Result<(), io::Error> {
+        let prefix = prefix.as_ref();
+
+        let files = read_archive(archive)?;
+
+        let last_modified = self.last_modified;
+
+        self.modify(|resources| {
+            for file in files {
+                let mime = mime_guess::from_path(&file.name).first_or_octet_stream();
+
+                let encoded = file
+                    .encoded
+                    .into_iter()
+                    .map(|(encoding, data)| {
+                        build_encoded_resource(encoding, ResourceData::from(data))
+                    })
+                    .collect();
+
+                resources.insert(
+                    Cow::Owned(format!("{}{}", prefix, file.name)),
+                    Arc::new(build_resource_with_variants(
+                        mime,
+                        ResourceData::from(file.data),
+                        encoded,
+                        file.modified.unwrap_or(last_modified),
+                    )),
+                );
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Register the files in a zip, tar or tar.gz archive file, such as one shipped next to the executable binary file, as resources. See `register_resource_archive`.
+    #[cfg(feature = "archive")]
+    #[inline]
+    pub fn register_resource_archive_file<S: AsRef<str>, P: AsRef<Path>>(
+        &mut self,
+        prefix: S,
+        archive_path: P,
+    ) -> Result<(), io::Error> {
+        self.register_resource_archive(prefix, &std::fs::read(archive_path)?)
+    }
+
+    /// Unregister a resource by a name. Returns `false` if the name is not registered. Like `register_resource`, it can be called at any time.
+    pub fn unregister_resource<S: AsRef<str>>(&self, name: S) -> bool {
         let name = name.as_ref();
 
-        self.resources.get(name).map(|resource| (&resource.mime, resource.data, &resource.etag))
+        let previous = self.resources.rcu(|resources| {
+            let mut resources = HashMap::clone(resources);
+
+            resources.remove(name);
+
+            resources
+        });
+
+        previous.contains_key(name)
+    }
+
+    /// Register static resources embedded from a directory by `embed_directory!`. Each file is named by `prefix` followed by its relative path, and its MIME type is guessed from the path.
+    ///
+    /// Each file comes with its encoded variants (its sidecar files, such as **app.js.br** next to **app.js**, or data compressed at compile time), its digests if they have been computed at compile time, and its modification time at compile time.
+    pub fn register_resource_directory_static<S: AsRef<str>>(
+        &mut self,
+        prefix: S,
+        files: &[EmbeddedDirectoryFile],
+    ) {
+        let prefix = prefix.as_ref();
+
+        let last_modified = self.last_modified;
+
+        self.modify(|resources| {
+            for &(relative_path, data, digests, variants, modified) in files {
+                let last_modified = embedded_last_modified(modified, last_modified);
+
+                let mime = mime_guess::from_path(relative_path).first_or_octet_stream();
+
+                resources.insert(
+                    Cow::Owned(format!("{}{}", prefix, relative_path)),
+                    Arc::new(build_embedded_resource(mime, data, digests, variants, last_modified)),
+                );
+            }
+        });
+    }
+
+    /// Register precompressed data (e.g. the content of a `.br` or `.gz` file) for a registered static resource. It replaces the data compressed by this crate. Returns `false` if the name is not registered.
+    #[inline]
+    pub fn register_resource_static_encoded<S: AsRef<str>>(
+        &mut self,
+        name: S,
+        encoding: Encoding,
+        data: &'static [u8],
+    ) -> bool {
+        self.modify(|resources| match resources.get_mut(name.as_ref()) {
+            Some(resource) => {
+                let resource = Arc::make_mut(resource);
+
+                let encoded = build_embedded_encoded_resource(encoding, data, None);
+
+                match resource.encoded.iter_mut().find(|e| e.encoding == encoding) {
+                    Some(e) => *e = encoded,
+                    None => resource.encoded.push(encoded),
+                }
+
+                true
+            },
+            None => false,
+        })
+    }
+
+    /// Set the `Cache-Control` policy of a registered static resource. Returns `false` if the name is not registered.
+    #[inline]
+    pub fn set_cache_policy<S: AsRef<str>>(&mut self, name: S, cache_policy: CachePolicy) -> bool {
+        self.modify(|resources| match resources.get_mut(name.as_ref()) {
+            Some(resource) => {
+                Arc::make_mut(resource).cache_policy = cache_policy;
+
+                true
+            },
+            None => false,
+        })
+    }
+
+    /// Set the `Cache-Control` policy of every registered static resource whose name starts with `prefix`, such as the files registered by `register_resource_directory_static`.
+    #[inline]
+    pub fn set_directory_cache_policy<S: AsRef<str>>(
+        &mut self,
+        prefix: S,
+        cache_policy: CachePolicy,
+    ) {
+        let prefix = prefix.as_ref();
+
+        self.modify(|resources| {
+            for (name, resource) in resources.iter_mut() {
+                if name.starts_with(prefix) {
+                    Arc::make_mut(resource).cache_policy = cache_policy;
+                }
+            }
+        })
+    }
+
+    /// Set the override directory. If a file in it has the same relative path (separated by `/`) as the name of a registered resource, it is served instead of the embedded data, with its own ETag and `Last-Modified` time. Overriding files are served without encoded variants.
+    ///
+    /// The directory is read when the fairing ignites, and again whenever `rescan_overrides` is called or, on Unix, the process receives `SIGHUP`. The `static_resources_override_dir` parameter in **Rocket.toml** takes precedence over this.
+    #[inline]
+    pub fn set_override_directory<P: Into<PathBuf>>(&mut self, directory: P) {
+        self.overrides = Some(Arc::new(Overrides::new(directory.into())));
+    }
+
+    /// Set which digest headers (`Repr-Digest` and `Content-Digest`) are sent with the resources. No digest headers are sent by default.
+    #[cfg(feature = "integrity")]
+    #[inline]
+    pub fn set_digest_headers(&mut self, digest_headers: DigestHeaders) {
+        self.digest_headers = digest_headers;
+    }
+
+    #[cfg(feature = "integrity")]
+    #[inline]
+    pub(crate) fn digest_headers(&self) -> DigestHeaders {
+        self.digest_headers
+    }
+
+    /// Resources are embedded in the release profile, so nothing is streamed from the disk and this does nothing.
+    #[inline]
+    pub fn set_stream_threshold(&mut self, _threshold: u64) {}
+
+    /// Resources are embedded in the release profile, so files are never checked and this does nothing.
+    #[inline]
+    pub fn set_content_hash_check(&mut self, _enabled: bool) {}
+
+    /// Resources are never reloaded in the release profile, so this does nothing.
+    #[inline]
+    pub fn reload_if_needed(&self) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    /// Get the override directory.
+    #[inline]
+    pub fn override_directory(&self) -> Option<&Path> {
+        self.overrides.as_deref().map(Overrides::directory)
+    }
+
+    /// Read the override directory again and return the number of files in it. If any file cannot be read, the previous files are kept. Returns `Ok(0)` if there is no override directory.
+    #[inline]
+    pub fn rescan_overrides(&self) -> Result<usize, io::Error> {
+        match self.overrides.as_deref() {
+            Some(overrides) => overrides.rescan(),
+            None => Ok(0),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn overrides(&self) -> Option<&Arc<Overrides>> {
+        self.overrides.as_ref()
+    }
+
+    #[inline]
+    pub(crate) fn get_override(&self, name: &str) -> Option<OverrideFile> {
+        self.overrides.as_deref().and_then(|overrides| overrides.get(name))
+    }
+
+    /// Get the time this crate was built, which is the `Last-Modified` time of the resources registered by `register_resource_static`. Embedded files and files in archives have their modification times instead, and resources registered by `register_resource` have the time they are registered.
+    #[inline]
+    pub fn last_modified(&self) -> SystemTime {
+        self.last_modified
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, name: &str) -> Option<Arc<Resource>> {
+        self.resources.load().get(name).cloned()
+    }
+}
+
+/// Build a resource from data given at runtime. If the data is compressible, it is also compressed with every enabled encoding.
+#[inline]
+fn build_resource(mime: Mime, data: ResourceData, last_modified: SystemTime) -> Resource {
+    build_resource_with_variants(mime, data, Vec::new(), last_modified)
+}
+
+/// Build a resource from data given at runtime with its precompressed variants. If the data is compressible, it is also compressed with every other enabled encoding.
+fn build_resource_with_variants(
+    mime: Mime,
+    data: ResourceData,
+    mut encoded: Vec<EncodedResource>,
+    last_modified: SystemTime,
+) -> Resource {
+    if is_compressible(&mime) {
+        for &encoding in Encoding::ALL.iter() {
+            if encoded.iter().any(|e| e.encoding == encoding) {
+                continue;
+            }
+
+            if let Some(data) = encoding.compress(&data) {
+                encoded.push(build_encoded_resource(encoding, ResourceData::from(data)));
+            }
+        }
+    }
+
+    new_resource(mime, data, None, encoded, last_modified)
+}
+
+/// Read a resource and its sidecar files from the disk.
+fn read_resource_file(path: PathBuf) -> Result<Resource, io::Error> {
+    let data = fs::read(&path)?;
+
+    let last_modified = path
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .unwrap_or_else(|_| SystemTime::now());
+
+    let mime = mime_guess::from_path(&path).first_or_octet_stream();
+
+    let encoded = Encoding::ALL
+        .iter()
+        .filter_map(|&encoding| {
+            let data = fs::read(encoding.sidecar_path(&path)).ok()?;
+
+            Some(build_encoded_resource(encoding, ResourceData::from(data)))
+        })
+        .collect();
+
+    Ok(Resource {
+        path: Some(path),
+        ..build_resource_with_variants(mime, ResourceData::from(data), encoded, last_modified)
+    })
+}
+
+#[inline]
+fn build_encoded_resource(encoding: Encoding, data: ResourceData) -> EncodedResource {
+    EncodedResource {
+        encoding,
+        etag: compute_data_etag(&*data),
+        #[cfg(feature = "integrity")]
+        integrity: Integrity::from_data(&*data),
+        data,
+    }
+}
+
+/// Build a resource embedded by `embed_file!` or `embed_directory!`. Its encoded variants come from the compile time, so nothing is compressed here.
+fn build_embedded_resource(
+    mime: Mime,
+    data: &'static [u8],
+    digests: EmbeddedDigests,
+    variants: EmbeddedVariants,
+    last_modified: SystemTime,
+) -> Resource {
+    let encoded = variants
+        .iter()
+        .filter_map(|&(token, data, digests)| {
+            Some(build_embedded_encoded_resource(Encoding::from_token(token)?, data, digests))
+        })
+        .collect();
+
+    new_resource(mime, ResourceData::Static(data), digests, encoded, last_modified)
+}
+
+#[cfg_attr(not(feature = "integrity"), allow(unused_variables))]
+#[inline]
+fn build_embedded_encoded_resource(
+    encoding: Encoding,
+    data: &'static [u8],
+    digests: EmbeddedDigests,
+) -> EncodedResource {
+    EncodedResource {
+        encoding,
+        data: ResourceData::Static(data),
+        etag: compute_data_etag(data),
+        #[cfg(feature = "integrity")]
+        integrity: embedded_integrity(data, digests),
+    }
+}
+
+/// Build a resource with its encoded variants. Its digests are computed unless they have been computed at compile time.
+#[cfg_attr(not(feature = "integrity"), allow(unused_variables))]
+#[inline]
+fn new_resource(
+    mime: Mime,
+    data: ResourceData,
+    digests: EmbeddedDigests,
+    encoded: Vec<EncodedResource>,
+    last_modified: SystemTime,
+) -> Resource {
+    let etag = compute_data_etag(&*data);
+
+    Resource {
+        #[cfg(feature = "integrity")]
+        integrity: embedded_integrity(&data, digests),
+        mime,
+        data,
+        etag,
+        encoded,
+        cache_policy: CachePolicy::new(),
+        last_modified,
+        path: None,
+    }
+}
+
+impl ResourceStore for StaticResources {
+    #[inline]
+    fn get_resource(&self, name: &str) -> Result<ResourceView, Error> {
+        let resource = self.get(name).ok_or_else(|| Error::NotFound(name.to_string()))?;
+
+        if let Some(file) = self.get_override(name) {
+            return Ok(ResourceView {
+                mime:          resource.mime.clone(),
+                data:          ResourceData::Shared(file.data),
+                etag:          file.etag,
+                encoding:      None,
+                last_modified: file.last_modified,
+            });
+        }
+
+        Ok(ResourceView {
+            mime:          resource.mime.clone(),
+            data:          resource.data.clone(),
+            etag:          resource.etag.clone(),
+            encoding:      None,
+            last_modified: Some(resource.last_modified),
+        })
+    }
+
+    #[inline]
+    fn get_resource_encoded(
+        &self,
+        name: &str,
+        encoding: Encoding,
+    ) -> Result<Option<ResourceView>, Error> {
+        let resource = self.get(name).ok_or_else(|| Error::NotFound(name.to_string()))?;
+
+        // overriding files have no encoded variants
+        if self.get_override(name).is_some() {
+            return Ok(None);
+        }
+
+        Ok(resource.encoded.iter().find(|e| e.encoding == encoding).map(|e| ResourceView {
+            mime:          resource.mime.clone(),
+            data:          e.data.clone(),
+            etag:          e.etag.clone(),
+            encoding:      Some(encoding),
+            last_modified: Some(resource.last_modified),
+        }))
+    }
+
+    #[inline]
+    fn resource_names(&self) -> Vec<String> {
+        self.resources.load().keys().map(|name| name.to_string()).collect()
     }
 }
 
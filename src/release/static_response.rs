@@ -1,65 +1,213 @@
-use std::io::Cursor;
-
-use mime::Mime;
+use std::{io::Cursor, ops::Range, time::SystemTime};
 
+use super::{
+    overrides::OverrideFile,
+    static_resources::{EncodedResource, Resource},
+};
 use crate::{
+    conditional::{evaluate_preconditions, set_validators, Precondition},
+    encoding::{accepted_encodings, Encoding},
+    range::apply_range,
     rocket::{
         http::Status,
         request::Request,
         response::{self, Responder, Response},
     },
-    EntityTag,
+    CachePolicy, EntityTag, EtagIfNoneMatch, ResourceData,
 };
+#[cfg(feature = "integrity")]
+use crate::{DigestHeaders, Integrity};
 
+/// A part of the data, used as the body of a response.
 #[derive(Debug)]
-struct StaticResponseInner {
-    mime: String,
-    data: &'static [u8],
-    etag: String,
+struct DataSlice {
+    data:  ResourceData,
+    range: Range<usize>,
+}
+
+impl AsRef<[u8]> for DataSlice {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.data[self.range.clone()]
+    }
+}
+
+#[derive(Debug)]
+struct StaticResponseVariant {
+    encoding:  Encoding,
+    data:      ResourceData,
+    etag:      EntityTag<'static>,
+    #[cfg(feature = "integrity")]
+    integrity: Integrity,
+}
+
+impl StaticResponseVariant {
+    #[inline]
+    fn new(encoded: &EncodedResource) -> StaticResponseVariant {
+        #[cfg(feature = "integrity")]
+        let integrity = encoded.integrity.clone();
+
+        StaticResponseVariant {
+            encoding: encoded.encoding,
+            data: encoded.data.clone(),
+            etag: encoded.etag.clone(),
+            #[cfg(feature = "integrity")]
+            integrity,
+        }
+    }
 }
 
 #[derive(Debug)]
 /// To respond a static resource.
 pub struct StaticResponse {
-    inner: Option<StaticResponseInner>,
+    mime:           String,
+    data:           ResourceData,
+    etag:           EntityTag<'static>,
+    encoded:        Vec<StaticResponseVariant>,
+    last_modified:  Option<SystemTime>,
+    if_none_match:  Option<EntityTag<'static>>,
+    cache_policy:   CachePolicy,
+    #[cfg(feature = "integrity")]
+    integrity:      Integrity,
+    #[cfg(feature = "integrity")]
+    digest_headers: DigestHeaders,
 }
 
 impl StaticResponse {
     #[inline]
     pub(crate) fn build(
-        mime: &Mime,
-        data: &'static [u8],
-        etag: &EntityTag<'static>,
+        resource: &Resource,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
     ) -> StaticResponse {
+        #[cfg(feature = "integrity")]
+        let integrity = resource.integrity.clone();
+        #[cfg(feature = "integrity")]
+        let digest_headers = DigestHeaders::new();
+
         StaticResponse {
-            inner: Some(StaticResponseInner {
-                mime: mime.to_string(),
-                data,
-                etag: etag.to_string(),
-            }),
+            mime: resource.mime.to_string(),
+            data: resource.data.clone(),
+            etag: resource.etag.clone(),
+            encoded: resource.encoded.iter().map(StaticResponseVariant::new).collect(),
+            last_modified: Some(resource.last_modified),
+            if_none_match: etag_if_none_match.etag.clone().map(EntityTag::into_owned),
+            cache_policy: resource.cache_policy,
+            #[cfg(feature = "integrity")]
+            integrity,
+            #[cfg(feature = "integrity")]
+            digest_headers,
         }
     }
 
+    /// Build a response from a file which overrides the embedded data of the resource.
     #[inline]
-    pub(crate) const fn not_modified() -> StaticResponse {
+    pub(crate) fn build_override(
+        resource: &Resource,
+        file: OverrideFile,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+    ) -> StaticResponse {
+        #[cfg(feature = "integrity")]
+        let integrity = file.integrity;
+        #[cfg(feature = "integrity")]
+        let digest_headers = DigestHeaders::new();
+
         StaticResponse {
-            inner: None
+            mime: resource.mime.to_string(),
+            data: ResourceData::Shared(file.data),
+            etag: file.etag,
+            encoded: Vec::new(),
+            last_modified: file.last_modified,
+            if_none_match: etag_if_none_match.etag.clone().map(EntityTag::into_owned),
+            cache_policy: resource.cache_policy,
+            #[cfg(feature = "integrity")]
+            integrity,
+            #[cfg(feature = "integrity")]
+            digest_headers,
         }
     }
+
+    /// Set which digest headers are sent.
+    #[cfg(feature = "integrity")]
+    #[inline]
+    pub(crate) fn with_digest_headers(mut self, digest_headers: DigestHeaders) -> StaticResponse {
+        self.digest_headers = digest_headers;
+
+        self
+    }
+}
+
+/// Set the `Cache-Control` header, which is needed by both `200 OK` and `304 Not Modified` responses.
+#[inline]
+fn set_cache_control(response: &mut response::Builder<'_>, cache_policy: &CachePolicy) {
+    if !cache_policy.is_empty() {
+        response.raw_header("Cache-Control", cache_policy.to_string());
+    }
 }
 
 impl<'r, 'o: 'r> Responder<'r, 'o> for StaticResponse {
     #[inline]
-    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'o> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'o> {
         let mut response = Response::build();
 
-        if let Some(inner) = self.inner {
-            response.raw_header("Etag", inner.etag.to_string());
-            response.raw_header("Content-Type", inner.mime.to_string());
+        let variant = accepted_encodings(request.headers().get_one("Accept-Encoding"))
+            .into_iter()
+            .find_map(|encoding| self.encoded.iter().find(|e| e.encoding == encoding));
+
+        #[cfg(feature = "integrity")]
+        let integrity = variant.map(|variant| &variant.integrity).unwrap_or(&self.integrity);
+
+        let (encoding, data, etag) = match variant {
+            Some(variant) => (Some(variant.encoding), variant.data.clone(), &variant.etag),
+            None => (None, self.data, &self.etag),
+        };
+
+        if !self.encoded.is_empty() {
+            response.raw_header("Vary", "Accept-Encoding");
+        }
+
+        match evaluate_preconditions(request, self.if_none_match.as_ref(), etag, self.last_modified)
+        {
+            Precondition::Failed => {
+                response.status(Status::PreconditionFailed);
+            },
+            Precondition::NotModified => {
+                response.status(Status::NotModified);
+
+                set_validators(&mut response, etag, self.last_modified);
+                set_cache_control(&mut response, &self.cache_policy);
+            },
+            Precondition::Proceed => {
+                set_validators(&mut response, etag, self.last_modified);
+                set_cache_control(&mut response, &self.cache_policy);
+
+                if let Some(encoding) = encoding {
+                    response.raw_header("Content-Encoding", encoding.as_str());
+                }
+
+                if let Some(range) = apply_range(
+                    &mut response,
+                    request,
+                    &self.mime,
+                    etag,
+                    self.last_modified,
+                    data.len(),
+                    Some(&data),
+                ) {
+                    #[cfg(feature = "integrity")]
+                    self.digest_headers.apply(
+                        &mut response,
+                        Some(integrity),
+                        range.len() == data.len(),
+                    );
+
+                    let data = DataSlice {
+                        data,
+                        range,
+                    };
 
-            response.sized_body(inner.data.len(), Cursor::new(inner.data));
-        } else {
-            response.status(Status::NotModified);
+                    response.sized_body(data.range.len(), Cursor::new(data));
+                }
+            },
         }
 
         response.ok()
@@ -0,0 +1,155 @@
+use std::{ops::Deref, sync::Arc, time::SystemTime};
+
+use crate::{encoding::Encoding, mime::Mime, EntityTag, Error};
+
+/// The data of a resource, which is either embedded into the executable binary file or loaded at runtime.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum ResourceData {
+    /// Data embedded into the executable binary file.
+    Static(&'static [u8]),
+    /// Data loaded or generated at runtime and shared with the store.
+    Shared(Arc<Vec<u8>>),
+    /// Data held by `bytes::Bytes`.
+    #[cfg(feature = "bytes")]
+    Bytes(bytes::Bytes),
+}
+
+impl Deref for ResourceData {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        match self {
+            ResourceData::Static(data) => data,
+            ResourceData::Shared(data) => data.as_slice(),
+            #[cfg(feature = "bytes")]
+            ResourceData::Bytes(data) => data,
+        }
+    }
+}
+
+impl AsRef<[u8]> for ResourceData {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl From<&'static [u8]> for ResourceData {
+    #[inline]
+    fn from(data: &'static [u8]) -> Self {
+        ResourceData::Static(data)
+    }
+}
+
+impl From<&'static str> for ResourceData {
+    #[inline]
+    fn from(data: &'static str) -> Self {
+        ResourceData::Static(data.as_bytes())
+    }
+}
+
+impl From<Vec<u8>> for ResourceData {
+    #[inline]
+    fn from(data: Vec<u8>) -> Self {
+        ResourceData::Shared(Arc::new(data))
+    }
+}
+
+impl From<String> for ResourceData {
+    #[inline]
+    fn from(data: String) -> Self {
+        ResourceData::Shared(Arc::new(data.into_bytes()))
+    }
+}
+
+impl From<Box<[u8]>> for ResourceData {
+    #[inline]
+    fn from(data: Box<[u8]>) -> Self {
+        ResourceData::Shared(Arc::new(data.into_vec()))
+    }
+}
+
+impl From<Arc<Vec<u8>>> for ResourceData {
+    #[inline]
+    fn from(data: Arc<Vec<u8>>) -> Self {
+        ResourceData::Shared(data)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl From<bytes::Bytes> for ResourceData {
+    #[inline]
+    fn from(data: bytes::Bytes) -> Self {
+        ResourceData::Bytes(data)
+    }
+}
+
+/// A snapshot of a resource (or one of its encoded variants), which is the same in the debug and release profiles. It does not borrow the store, so it stays valid even if the resource is reloaded later.
+#[derive(Debug, Clone)]
+pub struct ResourceView {
+    pub(crate) mime:          Mime,
+    pub(crate) data:          ResourceData,
+    pub(crate) etag:          EntityTag<'static>,
+    pub(crate) encoding:      Option<Encoding>,
+    pub(crate) last_modified: Option<SystemTime>,
+}
+
+impl ResourceView {
+    /// The MIME type of the resource. Encoded variants have the MIME type of the original resource.
+    #[inline]
+    pub fn mime(&self) -> &Mime {
+        &self.mime
+    }
+
+    /// The data, which is compressed if this is an encoded variant.
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Take the data.
+    #[inline]
+    pub fn into_data(self) -> ResourceData {
+        self.data
+    }
+
+    /// The ETag, which is computed from `data`.
+    #[inline]
+    pub fn etag(&self) -> &EntityTag<'static> {
+        &self.etag
+    }
+
+    /// The encoding of the data, or `None` for the original resource.
+    #[inline]
+    pub fn encoding(&self) -> Option<Encoding> {
+        self.encoding
+    }
+
+    /// The `Last-Modified` time of the resource, if it is known.
+    #[inline]
+    pub fn last_modified(&self) -> Option<SystemTime> {
+        self.last_modified
+    }
+}
+
+/// The common interface of the stores of static resources, which is implemented by `StaticResources` (the release profile), `FileResources` (the debug profile) and `StaticContextManager`, so that code using it compiles in both profiles.
+///
+/// In the debug profile, getting a resource reloads it if its file has been changed.
+pub trait ResourceStore {
+    /// Get the specific resource.
+    ///
+    /// Returns `Err(Error::NotFound)` if the name is not registered, or `Err(Error::IOError)` if the file cannot be read.
+    fn get_resource(&self, name: &str) -> Result<ResourceView, Error>;
+
+    /// Get the specific resource compressed with a specific encoding. Returns `Ok(None)` if the resource has no such variant.
+    fn get_resource_encoded(
+        &self,
+        name: &str,
+        encoding: Encoding,
+    ) -> Result<Option<ResourceView>, Error>;
+
+    /// The names of the registered resources, in no particular order.
+    fn resource_names(&self) -> Vec<String>;
+}
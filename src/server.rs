@@ -0,0 +1,342 @@
+use std::marker::PhantomData;
+
+use crate::{
+    fingerprint::{fingerprinted_name, split_fingerprint, IMMUTABLE_CACHE_CONTROL},
+    rocket::{
+        http::{Method, RawStr, Status},
+        response::{self, Redirect, Responder},
+        route::{Handler, Outcome, Route},
+        Data, Request,
+    },
+    Error, EtagIfNoneMatch, StaticContextManager, StaticResponse,
+};
+
+/// How `StaticServer` responds to requests whose fingerprints are outdated, which usually come from pages cached before the resources changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutdatedFingerprint {
+    /// Redirect to the up-to-date fingerprinted name with `307 Temporary Redirect`.
+    Redirect,
+    /// Serve the current content with `Cache-Control: no-cache`.
+    Serve,
+}
+
+/// A `StaticResponse` with a `Cache-Control` header.
+struct CacheControlled(StaticResponse, &'static str);
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for CacheControlled {
+    #[inline]
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'o> {
+        let mut response = self.0.respond_to(request)?;
+
+        response.set_raw_header("Cache-Control", self.1);
+
+        Ok(response)
+    }
+}
+
+/// A handler which serves the static resources registered in `StaticContextManager<N>` by mapping request paths to resource names. It can be used like `rocket::fs::FileServer`.
+///
+/// A request path relative to the mount point is prefixed with `base` to become a resource name. For example, if `StaticServer::new("assets/")` is mounted at `/static`, requesting `/static/js/app.js` responds the resource named **assets/js/app.js**. A path which is empty or ends with `/` is mapped to the **index.html** under it. Requests whose resources are not found, or whose paths contain `..` segments, are forwarded.
+///
+/// Fingerprinted names (see `StaticContextManager::fingerprinted_name`), such as **assets/js/app.{fingerprint}.js**, are resolved as well and served with `Cache-Control: public, max-age=31536000, immutable`. Requests with outdated fingerprints are handled according to `OutdatedFingerprint`, which is `OutdatedFingerprint::Redirect` by default.
+///
+/// ```rust,ignore
+/// rocket::build()
+///     .attach(static_resources_initializer!(
+///         "assets/" => dir("front-end/dist"),
+///     ))
+///     .mount("/static", StaticServer::new("assets/"))
+/// ```
+///
+/// The resources of another namespace are served by `StaticServer::new("assets/").namespace::<Admin>()`.
+#[derive(Debug)]
+pub struct StaticServer<N = ()> {
+    base:                 String,
+    rank:                 isize,
+    outdated_fingerprint: OutdatedFingerprint,
+    namespace:            PhantomData<fn() -> N>,
+}
+
+impl<N> Clone for StaticServer<N> {
+    #[inline]
+    fn clone(&self) -> Self {
+        StaticServer {
+            base:                 self.base.clone(),
+            rank:                 self.rank,
+            outdated_fingerprint: self.outdated_fingerprint,
+            namespace:            PhantomData,
+        }
+    }
+}
+
+impl StaticServer {
+    /// The default rank of the route, which is the same as `rocket::fs::FileServer`.
+    pub const DEFAULT_RANK: isize = 10;
+
+    /// Create a `StaticServer` which maps request paths to resource names prefixed with `base`.
+    #[inline]
+    pub fn new<S: Into<String>>(base: S) -> StaticServer {
+        StaticServer {
+            base:                 base.into(),
+            rank:                 Self::DEFAULT_RANK,
+            outdated_fingerprint: OutdatedFingerprint::Redirect,
+            namespace:            PhantomData,
+        }
+    }
+}
+
+impl<N> StaticServer<N> {
+    /// Serve the resources of the namespace `M`, which are managed by `StaticContextManager<M>`.
+    #[inline]
+    pub fn namespace<M>(self) -> StaticServer<M> {
+        StaticServer {
+            base:                 self.base,
+            rank:                 self.rank,
+            outdated_fingerprint: self.outdated_fingerprint,
+            namespace:            PhantomData,
+        }
+    }
+
+    /// Set the rank of the route.
+    #[inline]
+    pub fn rank(mut self, rank: isize) -> StaticServer<N> {
+        self.rank = rank;
+
+        self
+    }
+
+    /// Set how to respond to requests whose fingerprints are outdated.
+    #[inline]
+    pub fn outdated_fingerprint(
+        mut self,
+        outdated_fingerprint: OutdatedFingerprint,
+    ) -> StaticServer<N> {
+        self.outdated_fingerprint = outdated_fingerprint;
+
+        self
+    }
+
+    async fn handle_fingerprinted<'r>(
+        &self,
+        request: &'r Request<'_>,
+        data: Data<'r>,
+        static_resources: &StaticContextManager<N>,
+        name: &str,
+        fingerprint: &str,
+    ) -> Outcome<'r> {
+        let current_fingerprint = match static_resources.fingerprint_async(name).await {
+            Ok(current_fingerprint) => current_fingerprint,
+            Err(Error::NotFound(_)) => return Outcome::forward(data, Status::NotFound),
+            Err(err) => return Outcome::from(request, err),
+        };
+
+        let cache_control = if current_fingerprint == fingerprint {
+            IMMUTABLE_CACHE_CONTROL
+        } else {
+            match self.outdated_fingerprint {
+                OutdatedFingerprint::Redirect => {
+                    let path = request.uri().path().as_str();
+
+                    let directory = &path[..path.rfind('/').map(|i| i + 1).unwrap_or(0)];
+                    let file_name = &name[name.rfind('/').map(|i| i + 1).unwrap_or(0)..];
+
+                    // the directory is taken from the request as it is, but names are not encoded
+                    let location = format!(
+                        "{}{}",
+                        directory,
+                        RawStr::new(&fingerprinted_name(file_name, &current_fingerprint))
+                            .percent_encode()
+                    );
+
+                    return Outcome::from(request, Redirect::temporary(location));
+                },
+                OutdatedFingerprint::Serve => "no-cache",
+            }
+        };
+
+        match static_resources.try_build_async(&EtagIfNoneMatch::default(), name).await {
+            Ok(response) => Outcome::from(request, CacheControlled(response, cache_control)),
+            Err(Error::NotFound(_)) => Outcome::forward(data, Status::NotFound),
+            Err(err) => Outcome::from(request, err),
+        }
+    }
+}
+
+impl<N: 'static> From<StaticServer<N>> for Vec<Route> {
+    #[inline]
+    fn from(server: StaticServer<N>) -> Self {
+        let mut route = Route::ranked(server.rank, Method::Get, "/<path..>", server);
+
+        route.name = Some("StaticServer".into());
+
+        vec![route]
+    }
+}
+
+#[rocket::async_trait]
+impl<N: 'static> Handler for StaticServer<N> {
+    async fn handle<'r>(&self, request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r> {
+        let static_resources = match request.rocket().state::<StaticContextManager<N>>() {
+            Some(static_resources) => static_resources,
+            None => return Outcome::error(Status::InternalServerError),
+        };
+
+        let mut name = self.base.clone();
+        let mut is_directory = true;
+
+        for segment in request.routed_segments(0..) {
+            // resource names are never normalized, so a name with `..` would never be found anyway
+            if segment == ".." {
+                return Outcome::forward(data, Status::NotFound);
+            }
+
+            if !is_directory {
+                name.push('/');
+            }
+
+            name.push_str(segment);
+
+            is_directory = false;
+        }
+
+        if is_directory || request.uri().path().ends_with('/') {
+            if !name.is_empty() && !name.ends_with('/') {
+                name.push('/');
+            }
+
+            name.push_str("index.html");
+        }
+
+        // fingerprinted names are resolved without looking them up, which would scan the directories for them in the debug profile
+        if let Some((original_name, fingerprint)) = split_fingerprint(&name) {
+            if static_resources.contains(&original_name) && !static_resources.contains(&name) {
+                return self
+                    .handle_fingerprinted(
+                        request,
+                        data,
+                        static_resources,
+                        &original_name,
+                        fingerprint,
+                    )
+                    .await;
+            }
+        }
+
+        match static_resources.try_build_async(&EtagIfNoneMatch::default(), &name).await {
+            Ok(response) => Outcome::from(request, response),
+            Err(Error::NotFound(_)) => Outcome::forward(data, Status::NotFound),
+            Err(err) => Outcome::from(request, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fingerprint::split_fingerprint,
+        mime,
+        rocket::{self, get, http::ContentType, local::blocking::Client, routes},
+        StaticResponse,
+    };
+
+    #[get("/static/<_..>", rank = 20)]
+    fn fallback() -> &'static str {
+        "fallback"
+    }
+
+    fn client() -> Client {
+        let rocket = rocket::custom(rocket::Config::figment().merge(("log_level", "off")))
+            .attach(
+                crate::static_resources_initializer!(
+                    "images/" => dir("examples/front-end/images"),
+                    "images/html/index.html" => "examples/front-end/html/README.html",
+                )
+                .merge(StaticResponse::fairing(|resources| {
+                    resources.register_resource(
+                        "images/a b#ü.css",
+                        mime::TEXT_CSS,
+                        b"body {}".to_vec(),
+                    )
+                })),
+            )
+            .mount("/static", StaticServer::new("images/"))
+            .mount("/", routes![fallback]);
+
+        Client::debug(rocket).unwrap()
+    }
+
+    #[test]
+    fn request_paths_are_mapped_to_resource_names() {
+        let client = client();
+
+        let response = client.get("/static/favicon.ico").dispatch();
+
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(
+            std::fs::read("examples/front-end/images/favicon.ico").unwrap(),
+            response.into_bytes().unwrap()
+        );
+
+        let index = client.get("/static/html/index.html").dispatch();
+
+        assert_eq!(Status::Ok, index.status());
+        assert_eq!(Some(ContentType::HTML), index.content_type());
+
+        let index = index.into_bytes().unwrap();
+
+        let response = client.get("/static/html/").dispatch();
+
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(index, response.into_bytes().unwrap());
+    }
+
+    #[test]
+    fn unknown_names_are_forwarded() {
+        let client = client();
+
+        for uri in ["/static/missing.png", "/static/html/missing/"] {
+            assert_eq!("fallback", client.get(uri).dispatch().into_string().unwrap());
+        }
+    }
+
+    #[test]
+    fn parent_segments_are_rejected() {
+        let client = client();
+
+        for uri in [
+            "/static/html/../favicon.ico",
+            "/static/html/%2E%2E/favicon.ico",
+            "/static/../images/favicon.ico",
+        ] {
+            assert_eq!("fallback", client.get(uri).dispatch().into_string().unwrap(), "{}", uri);
+        }
+    }
+
+    #[test]
+    fn outdated_fingerprints_are_redirected_to_encoded_names() {
+        let client = client();
+
+        let fingerprinted_name = client
+            .rocket()
+            .state::<StaticContextManager>()
+            .unwrap()
+            .fingerprinted_name("images/a b#ü.css")
+            .unwrap();
+
+        let (_, fingerprint) = split_fingerprint(&fingerprinted_name).unwrap();
+
+        let response = client.get("/static/a%20b%23%C3%BC.AAAAAAAAAAA.css").dispatch();
+
+        let location = format!("/static/a%20b%23%C3%BC.{}.css", fingerprint);
+
+        assert_eq!(Status::TemporaryRedirect, response.status());
+        assert_eq!(Some(location.as_str()), response.headers().get_one("Location"));
+
+        let response = client.get(location).dispatch();
+
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(Some(IMMUTABLE_CACHE_CONTROL), response.headers().get_one("Cache-Control"));
+        assert_eq!("body {}", response.into_string().unwrap());
+    }
+}
@@ -0,0 +1,48 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, JsonValue, RenderContext, RenderError,
+    RenderErrorReason, ScopedJson,
+};
+
+use super::{TemplateFunction, TemplateHelpers};
+
+/// A helper registered as a Handlebars helper, which takes the name of a resource as its parameter, e.g. `{{static_url "assets/js/app.js"}}`.
+struct HandlebarsHelper<N> {
+    helpers:  TemplateHelpers<N>,
+    function: TemplateFunction,
+}
+
+impl<N> HelperDef for HandlebarsHelper<N> {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let name = h
+            .param(0)
+            .and_then(|param| param.value().as_str())
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex(self.function.name(), 0))?;
+
+        self.helpers
+            .call(self.function, name)
+            .map(|value| ScopedJson::Derived(JsonValue::String(value)))
+            .map_err(|err| RenderErrorReason::Other(err).into())
+    }
+}
+
+impl<N: 'static> TemplateHelpers<N> {
+    /// Register `static_url`, `static_integrity` and `static_inline` as Handlebars helpers, which are called like `{{static_url "assets/js/app.js"}}`. Use `{{{static_inline "..."}}}` to render a resource without escaping.
+    #[inline]
+    pub fn register_handlebars(&self, handlebars: &mut Handlebars<'_>) {
+        for function in TemplateFunction::ALL {
+            handlebars.register_helper(
+                function.name(),
+                Box::new(HandlebarsHelper {
+                    helpers: self.clone(),
+                    function,
+                }),
+            );
+        }
+    }
+}
@@ -0,0 +1,202 @@
+#[cfg(feature = "handlebars")]
+mod handlebars_helpers;
+#[cfg(feature = "tera")]
+mod tera_functions;
+
+use std::{
+    any::type_name,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    rocket::{
+        fairing::{self, Fairing, Info, Kind},
+        Build, Rocket,
+    },
+    ResourceStore, StaticContextManager,
+};
+
+const FAIRING_NAME: &str = "Static Resources (Template Helpers)";
+
+/// The helpers which template engines use to refer to static resources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TemplateFunction {
+    /// The URL of a resource served by `StaticServer`, with a fingerprinted name.
+    Url,
+    /// The Subresource Integrity metadata of a resource.
+    Integrity,
+    /// The content of a resource, which must be UTF-8.
+    Inline,
+}
+
+impl TemplateFunction {
+    const ALL: [TemplateFunction; 3] =
+        [TemplateFunction::Url, TemplateFunction::Integrity, TemplateFunction::Inline];
+
+    /// The name of the helper in templates.
+    #[inline]
+    fn name(self) -> &'static str {
+        match self {
+            TemplateFunction::Url => "static_url",
+            TemplateFunction::Integrity => "static_integrity",
+            TemplateFunction::Inline => "static_inline",
+        }
+    }
+
+    /// Whether the output can be rendered without escaping.
+    #[cfg(feature = "tera")]
+    #[inline]
+    fn is_safe(self) -> bool {
+        self == TemplateFunction::Inline
+    }
+}
+
+/// Helpers of template engines (Tera with the `tera` feature and Handlebars with the `handlebars` feature) which refer to the static resources in `StaticContextManager<N>`.
+///
+/// * `static_url` returns the URL of a resource with a fingerprinted name, which is served by the `StaticServer` described by `TemplateHelpers::new`.
+/// * `static_integrity` returns the Subresource Integrity metadata (e.g. **sha384-...**) of a resource, for the `integrity` attribute.
+/// * `static_inline` returns the content of a resource, such as an SVG image, which must be UTF-8.
+///
+/// The fairing returned by `TemplateHelpers::fairing` must be attached after the fairing of static resources.
+///
+/// ```rust,ignore
+/// let helpers = TemplateHelpers::new("/static", "assets/");
+///
+/// rocket::build()
+///     .attach(static_resources_initializer!(
+///         "assets/" => dir("front-end/dist"),
+///     ))
+///     .attach(helpers.fairing())
+///     .attach(Template::custom(move |engines| helpers.register_tera(&mut engines.tera)))
+///     .mount("/static", StaticServer::new("assets/"))
+/// ```
+#[derive(Debug)]
+pub struct TemplateHelpers<N = ()> {
+    manager: Arc<RwLock<Option<StaticContextManager<N>>>>,
+    mount:   String,
+    base:    String,
+}
+
+impl<N> Clone for TemplateHelpers<N> {
+    #[inline]
+    fn clone(&self) -> Self {
+        TemplateHelpers {
+            manager: self.manager.clone(),
+            mount:   self.mount.clone(),
+            base:    self.base.clone(),
+        }
+    }
+}
+
+impl TemplateHelpers {
+    /// Create helpers whose URLs point to `StaticServer::new(base)` mounted at `mount`, such as `TemplateHelpers::new("/static", "assets/")` for `.mount("/static", StaticServer::new("assets/"))`.
+    #[inline]
+    pub fn new<M: Into<String>, B: Into<String>>(mount: M, base: B) -> TemplateHelpers {
+        TemplateHelpers {
+            manager: Arc::new(RwLock::new(None)),
+            mount:   mount.into(),
+            base:    base.into(),
+        }
+    }
+}
+
+impl<N> TemplateHelpers<N> {
+    /// Refer to the resources of the namespace `M`, which are managed by `StaticContextManager<M>`.
+    #[inline]
+    pub fn namespace<M>(self) -> TemplateHelpers<M> {
+        TemplateHelpers {
+            manager: Arc::new(RwLock::new(None)),
+            mount:   self.mount,
+            base:    self.base,
+        }
+    }
+
+    /// Create the fairing which connects the helpers to `StaticContextManager<N>`. It must be attached after the fairing of static resources.
+    #[inline]
+    pub fn fairing(&self) -> impl Fairing
+    where
+        N: 'static, {
+        TemplateHelpersFairing {
+            helpers: self.clone()
+        }
+    }
+
+    fn call(&self, function: TemplateFunction, name: &str) -> Result<String, String> {
+        let manager = self.manager.read().unwrap_or_else(|err| err.into_inner());
+
+        let manager = manager.as_ref().ok_or_else(|| {
+            format!(
+                "`{}` cannot be used because the fairing of template helpers is not attached.",
+                function.name()
+            )
+        })?;
+
+        match function {
+            TemplateFunction::Url => {
+                if !name.starts_with(self.base.as_str()) {
+                    return Err(format!(
+                        "The static resource `{}` is not under `{}`, so it has no URL.",
+                        name, self.base
+                    ));
+                }
+
+                let fingerprinted_name =
+                    manager.fingerprinted_name(name).map_err(|err| err.to_string())?;
+
+                // only the file name is changed, so the prefix is kept
+                Ok(format!(
+                    "{}/{}",
+                    self.mount.trim_end_matches('/'),
+                    &fingerprinted_name[self.base.len()..]
+                ))
+            },
+            TemplateFunction::Integrity => manager
+                .integrity(name)
+                .map(|integrity| integrity.to_string())
+                .map_err(|err| err.to_string()),
+            TemplateFunction::Inline => {
+                let resource = manager.get_resource(name).map_err(|err| err.to_string())?;
+
+                String::from_utf8(resource.data().to_vec()).map_err(|_| {
+                    format!("The static resource `{}` is not UTF-8, so it cannot be inlined.", name)
+                })
+            },
+        }
+    }
+}
+
+/// The fairing of `TemplateHelpers`.
+struct TemplateHelpersFairing<N> {
+    helpers: TemplateHelpers<N>,
+}
+
+#[rocket::async_trait]
+impl<N: 'static> Fairing for TemplateHelpersFairing<N> {
+    #[inline]
+    fn info(&self) -> Info {
+        Info {
+            name: FAIRING_NAME, kind: Kind::Ignite
+        }
+    }
+
+    #[inline]
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        let manager = match rocket.state::<StaticContextManager<N>>() {
+            Some(manager) => manager.share(),
+            None => {
+                rocket::error!(
+                    "The template helpers of static resources in the namespace `{}` need the \
+                     fairing of static resources with the same namespace, which must be attached \
+                     before `TemplateHelpers::fairing`.",
+                    type_name::<N>()
+                );
+
+                return Err(rocket);
+            },
+        };
+
+        *self.helpers.manager.write().unwrap_or_else(|err| err.into_inner()) = Some(manager);
+
+        Ok(rocket)
+    }
+}
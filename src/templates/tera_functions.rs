@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use tera::{Function, Tera, Value};
+
+use super::{TemplateFunction, TemplateHelpers};
+
+/// A helper registered as a Tera function, which takes the name of a resource by the `name` argument, e.g. `static_url(name="assets/js/app.js")`.
+struct TeraFunction<N> {
+    helpers:  TemplateHelpers<N>,
+    function: TemplateFunction,
+}
+
+impl<N> Function for TeraFunction<N> {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        let name = args.get("name").and_then(Value::as_str).ok_or_else(|| {
+            tera::Error::msg(format!(
+                "`{}` needs the name of a static resource as the `name` argument.",
+                self.function.name()
+            ))
+        })?;
+
+        self.helpers.call(self.function, name).map(Value::String).map_err(tera::Error::msg)
+    }
+
+    #[inline]
+    fn is_safe(&self) -> bool {
+        self.function.is_safe()
+    }
+}
+
+impl<N: 'static> TemplateHelpers<N> {
+    /// Register `static_url`, `static_integrity` and `static_inline` as Tera functions, which are called like `{{ static_url(name="assets/js/app.js") }}`. The output of `static_inline` is not escaped.
+    #[inline]
+    pub fn register_tera(&self, tera: &mut Tera) {
+        for function in TemplateFunction::ALL {
+            tera.register_function(function.name(), TeraFunction {
+                helpers: self.clone(),
+                function,
+            });
+        }
+    }
+}